@@ -1,22 +1,46 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    from_json, to_json_binary, to_json_vec, BankMsg, Binary, CosmosMsg, Deps, DepsMut, Empty, Env,
+    MessageInfo, Response, StdResult, Storage,
 };
 use cw2::set_contract_version;
 // use cw2::set_contract_version;
 
+use crate::cw20::{BalanceResponse, Cw20CoinVerified, Cw20QueryMsg, Cw20ReceiveMsg};
+use crate::cw4::{Cw4QueryMsg, MemberResponse};
+use crate::cw721::{Cw721QueryMsg, TokensResponse};
 use crate::error::ContractError;
 use crate::msg::{
-    ExecuteMsg, GetAllPollsResponse, GetPollResponse, GetUserVoteResponse, InstantiateMsg, QueryMsg,
+    ExecuteMsg, GetAllPollsResponse, GetDelegationResponse, GetFundedProjectsResponse,
+    GetPollResponse, GetPollResultResponse, GetRankingResponse, GetTokenVoteResponse,
+    GetUserVoteResponse, InstantiateMsg, PollSettings, QueryMsg, ReceiveMsg,
 };
-use crate::state::{Ballot, Config, Poll, BALLOTS, CONFIG, POLLS};
+use crate::state::{
+    Ballot, Config, Deposit, Poll, PollResult, BALLOTS, CONFIG, DELEGATIONS, DELEGATORS, ESCROWS,
+    GLOBAL_SCOPE, POLLS, PROXIES, RESULTS, TOKEN_BALLOTS,
+};
+use crate::tally;
 use cosmwasm_std::Addr;
+use cw_utils::{must_pay, nonpayable, Expiration, Scheduled};
+use sha2::{Digest, Sha256};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw_contract";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Most questions a single survey can hold
+const MAX_QUESTIONS: usize = 20;
+const MAX_OPTIONS: usize = 10;
+// pairwise polls are meant for option sets too large for anyone to rank whole
+const MAX_PAIRWISE_OPTIONS: usize = 100;
+const MAX_COMPARISONS: u32 = 50;
+// every delegator's power is queried when their delegate votes, this bounds the queries per
+// scope so nobody can make a delegate's votes run out of gas
+const MAX_DELEGATORS: u32 = 50;
+// ids ending in this are kept for the runoff polls the contract opens
+const RUNOFF_SUFFIX: &str = "-runoff";
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -26,12 +50,28 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    if msg.fee.denom.is_empty() {
+        return Err(ContractError::InvalidFee {});
+    }
+
     let admin = msg.admin.unwrap_or(info.sender.to_string());
     let validated_admin = deps.api.addr_validate(&admin)?;
 
+    let cw20_fee = msg
+        .cw20_fee
+        .map(|fee| -> StdResult<_> {
+            Ok(Cw20CoinVerified {
+                address: deps.api.addr_validate(&fee.address)?,
+                amount: fee.amount,
+            })
+        })
+        .transpose()?;
+
     let config = Config {
         admin: validated_admin.clone(),
-        fee: info.funds[0].clone(),
+        fee: msg.fee,
+        cw20_fee,
+        paused: false,
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -44,63 +84,302 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // cw20 hooks only create polls, so they stop along with everything else that moves funds
+    // or tallies
+    let pausable = matches!(
+        msg,
+        ExecuteMsg::CreatePoll { .. }
+            | ExecuteMsg::CreateSurvey { .. }
+            | ExecuteMsg::Receive(_)
+            | ExecuteMsg::Vote { .. }
+            | ExecuteMsg::Reveal { .. }
+            | ExecuteMsg::Delegate { .. }
+            | ExecuteMsg::ClosePoll { .. }
+            | ExecuteMsg::FinalizePoll { .. }
+            | ExecuteMsg::Execute { .. }
+    );
+    if pausable && CONFIG.load(deps.storage)?.paused {
+        return Err(ContractError::Paused {});
+    }
+
     match msg {
         ExecuteMsg::CreatePoll {
             poll_id,
             question,
             options,
-        } => execute::execute_create_poll(deps, info, poll_id, question, options),
-        ExecuteMsg::Vote { poll_id, vote } => execute::execute_vote(deps, info, poll_id, vote),
-        ExecuteMsg::ClosePoll { poll_id } => execute::execute_close_poll(deps, info, poll_id),
+            settings,
+        } => {
+            let deposit = execute::native_deposit(deps.as_ref(), &info)?;
+            execute::execute_create_poll(
+                deps,
+                env,
+                info.sender,
+                deposit,
+                poll_id,
+                question,
+                options,
+                None,
+                settings,
+            )
+        }
+        ExecuteMsg::CreateSurvey {
+            poll_id,
+            title,
+            questions,
+            settings,
+        } => {
+            let deposit = execute::native_deposit(deps.as_ref(), &info)?;
+            execute::execute_create_poll(
+                deps,
+                env,
+                info.sender,
+                deposit,
+                poll_id,
+                title,
+                vec![],
+                Some(questions),
+                settings,
+            )
+        }
+        ExecuteMsg::Receive(msg) => execute::execute_receive(deps, env, info, msg),
+        ExecuteMsg::Vote { poll_id, vote } => execute::execute_vote(deps, env, info, poll_id, vote),
+        ExecuteMsg::Reveal {
+            poll_id,
+            vote,
+            salt,
+        } => execute::execute_reveal(deps, env, info, poll_id, vote, salt),
+        ExecuteMsg::ClosePoll { poll_id } => execute::execute_close_poll(deps, env, info, poll_id),
+        ExecuteMsg::FinalizePoll { poll_id } => execute::execute_finalize_poll(deps, env, poll_id),
+        ExecuteMsg::Delegate { delegate, poll_id } => {
+            execute::execute_delegate(deps, env, info, delegate, poll_id)
+        }
+        ExecuteMsg::Execute { poll_id } => execute::execute_poll_actions(deps, env, poll_id),
+        ExecuteMsg::Cancel { poll_id, reason } => {
+            execute::execute_cancel_poll(deps, env, info, poll_id, reason)
+        }
+        ExecuteMsg::Veto { poll_id, reason } => {
+            execute::execute_veto_poll(deps, env, info, poll_id, reason)
+        }
+        ExecuteMsg::Pause {} => execute::execute_set_paused(deps, info, true),
+        ExecuteMsg::Unpause {} => execute::execute_set_paused(deps, info, false),
     }
 }
 
 pub mod execute {
 
-    use crate::state::BALLOTS;
+    use crate::msg::Question;
+    use crate::state::{
+        Actions, Choice, Decision, Outcome, QuestionResult, Quorum, ResultDetail, SurveyQuestion,
+        Threshold, VotingMethod, VotingPower, BALLOTS,
+    };
+    use cosmwasm_std::{Coin, Decimal, Decimal256, Order, Uint128, WasmMsg};
 
     use super::*;
 
-    pub fn execute_create_poll(
+    /// Checks the attached funds are exactly the native creation fee, free polls take no funds
+    pub fn native_deposit(deps: Deps, info: &MessageInfo) -> Result<Deposit, ContractError> {
+        let fee = CONFIG.load(deps.storage)?.fee;
+        if fee.amount.is_zero() {
+            nonpayable(info)?;
+            return Ok(Deposit::Native(fee));
+        }
+
+        let paid = must_pay(info, &fee.denom)?;
+        if paid < fee.amount {
+            return Err(ContractError::InsufficientFunds {});
+        }
+        if paid > fee.amount {
+            return Err(ContractError::Overpaid {
+                paid,
+                fee: fee.amount,
+            });
+        }
+        Ok(Deposit::Native(fee))
+    }
+
+    /// Handles a cw20 `Send`, the only thing the tokens can pay for is creating a poll
+    pub fn execute_receive(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
+        wrapper: Cw20ReceiveMsg,
+    ) -> Result<Response, ContractError> {
+        let fee = CONFIG
+            .load(deps.storage)?
+            .cw20_fee
+            .filter(|fee| fee.address == info.sender)
+            .ok_or(ContractError::UnsupportedToken {
+                address: info.sender.to_string(),
+            })?;
+        if wrapper.amount < fee.amount {
+            return Err(ContractError::InsufficientFunds {});
+        }
+        if wrapper.amount > fee.amount {
+            return Err(ContractError::Overpaid {
+                paid: wrapper.amount,
+                fee: fee.amount,
+            });
+        }
+
+        let creator = deps.api.addr_validate(&wrapper.sender)?;
+        match from_json(&wrapper.msg)? {
+            ReceiveMsg::CreatePoll {
+                poll_id,
+                question,
+                options,
+                settings,
+            } => execute_create_poll(
+                deps,
+                env,
+                creator,
+                Deposit::Cw20(fee.clone()),
+                poll_id,
+                question,
+                options,
+                None,
+                settings,
+            ),
+            ReceiveMsg::CreateSurvey {
+                poll_id,
+                title,
+                questions,
+                settings,
+            } => execute_create_poll(
+                deps,
+                env,
+                creator,
+                Deposit::Cw20(fee.clone()),
+                poll_id,
+                title,
+                vec![],
+                Some(questions),
+                settings,
+            ),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_create_poll(
+        deps: DepsMut,
+        env: Env,
+        creator: Addr,
+        deposit: Deposit,
         poll_id: String,
         question: String,
         options: Vec<String>,
+        questions: Option<Vec<Question>>,
+        settings: PollSettings,
     ) -> Result<Response, ContractError> {
-        let config = CONFIG.load(deps.storage)?;
-        if info.funds[0].denom != config.fee.denom || info.funds[0].amount < config.fee.amount {
-            return Err(ContractError::InsufficientFunds {});
+        // an existing poll's escrow, ballots and result are all keyed by its id
+        if POLLS.has(deps.storage, &poll_id) {
+            return Err(ContractError::PollExists { poll_id });
         }
-
-        if options.len() > 10 {
+        if poll_id.ends_with(RUNOFF_SUFFIX) {
+            return Err(ContractError::ReservedPollId { poll_id });
+        }
+        validate_schedule(&env, &settings)?;
+        validate_rules(&settings)?;
+        let method = match (&questions, settings.method) {
+            (None, method) => method.unwrap_or(VotingMethod::SingleChoice {}),
+            (Some(_), None) => VotingMethod::Survey {},
+            // survey questions each bring their own method
+            (Some(_), Some(_)) => return Err(ContractError::InvalidMethod {}),
+        };
+        let max_options = match method {
+            VotingMethod::Pairwise {} => MAX_PAIRWISE_OPTIONS,
+            _ => MAX_OPTIONS,
+        };
+        if options.len() > max_options {
             return Err(ContractError::TooManyOptions {});
         }
+        let questions = match questions {
+            Some(questions) => survey_questions(questions)?,
+            None => {
+                validate_method(&method, options.len())?;
+                vec![]
+            }
+        };
+        if settings.reveal_end.is_some() {
+            if let Some(VotingPower::Cw721Tokens { .. }) = settings.power {
+                return Err(ContractError::InvalidMethod {});
+            }
+        }
+        // a runoff needs two plain tallies to pick from, and secret tallies are empty at close
+        if settings.runoff.is_some()
+            && (method != VotingMethod::SingleChoice {}
+                || options.len() < 2
+                || settings.reveal_end.is_some())
+        {
+            return Err(ContractError::InvalidMethod {});
+        }
+        if let Some(actions) = &settings.actions {
+            // with a runoff the decision is left to another poll
+            if settings.runoff.is_some() {
+                return Err(ContractError::InvalidMethod {});
+            }
+            validate_actions(deps.as_ref(), &env, &method, &options, actions)?;
+        }
 
-        let mut opts: Vec<(String, u64)> = Vec::new();
+        let mut opts: Vec<(String, Uint128)> = Vec::new();
         let options_clone = options.clone();
 
         for option in options {
-            opts.push((option, 0));
+            opts.push((option, Uint128::zero()));
         }
 
+        let power = match settings.power {
+            Some(VotingPower::Cw20Balance { contract }) => VotingPower::Cw20Balance {
+                contract: deps.api.addr_validate(contract.as_str())?,
+            },
+            Some(VotingPower::Cw4Group { contract }) => VotingPower::Cw4Group {
+                contract: deps.api.addr_validate(contract.as_str())?,
+            },
+            Some(VotingPower::Cw721Tokens { contract }) => VotingPower::Cw721Tokens {
+                contract: deps.api.addr_validate(contract.as_str())?,
+            },
+            power => power.unwrap_or(VotingPower::Equal {}),
+        };
+
         let new_poll = Poll {
-            creator: info.sender.clone(),
+            creator: creator.clone(),
             question: question.clone(),
             options: opts,
             is_active: true,
+            deposit: deposit.clone(),
+            start: settings.start,
+            end: settings.end,
+            reveal_end: settings.reveal_end,
+            method,
+            power,
+            quorum: settings.quorum,
+            threshold: settings.threshold,
+            ballots: 0,
+            total_weight: Uint128::zero(),
+            comparisons: vec![],
+            questions,
+            runoff: settings.runoff,
+            runoff_poll: None,
+            runoff_of: None,
+            actions: settings.actions,
+            executed: false,
+            cancelled: None,
+            vetoed: None,
         };
 
         POLLS.save(deps.storage, &poll_id, &new_poll)?;
+        // free polls have nothing to refund
+        if !deposit.amount().is_zero() {
+            ESCROWS.save(deps.storage, &poll_id, &deposit)?;
+        }
 
         Ok(Response::new()
             .add_attribute("action", "create_poll")
             .add_attribute("poll_id", poll_id)
-            .add_attribute("creator", info.sender.to_string())
+            .add_attribute("creator", creator.to_string())
             .add_attribute("question", question)
             .add_attribute("options", options_clone.join(", "))
             .add_attribute("is_active", "true"))
@@ -108,9 +387,10 @@ pub mod execute {
 
     pub fn execute_vote(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         poll_id: String,
-        vote: String,
+        vote: Choice,
     ) -> Result<Response, ContractError> {
         let mut poll =
             POLLS
@@ -119,51 +399,177 @@ pub mod execute {
                     poll_id: poll_id.clone(),
                 })?;
 
-        if !poll.is_active {
+        if !poll.has_started(&env.block) {
+            return Err(ContractError::PollNotStarted { poll_id });
+        }
+        if poll.has_ended(&env.block) {
             return Err(ContractError::PollClosed { poll_id });
         }
 
-        BALLOTS.update(
-            deps.storage,
-            (info.sender.clone(), &poll_id),
-            |ballot| -> StdResult<Ballot> {
-                match ballot {
-                    Some(ballot) => {
-                        let old_position = poll
-                            .options
-                            .iter()
-                            .position(|option| option.0 == ballot.option)
-                            .unwrap();
-                        poll.options[old_position].1 -= 1;
-                        Ok(Ballot {
-                            option: vote.clone(),
-                        })
-                    }
-                    None => Ok(Ballot {
-                        option: vote.clone(),
-                    }),
-                }
-            },
-        )?;
+        if poll.reveal_end.is_some() {
+            return commit_vote(deps, info, poll_id, &poll, vote);
+        }
 
-        let vote_position = poll
-            .options
-            .iter()
-            .position(|(option, _)| option == &vote)
-            .ok_or(ContractError::InvalidVote {})?;
+        validate_choice(&poll, &vote)?;
+
+        let mut ballot = Ballot {
+            choice: vote.clone(),
+            weight: voting_power(deps.as_ref(), &poll_id, &poll, &info.sender)?,
+            delegators: vec![],
+        };
+
+        let (tokens, weight) = match &poll.power {
+            VotingPower::Cw721Tokens { contract } => {
+                let tokens = owned_tokens(deps.as_ref(), contract, &info.sender)?;
+                let weight = ballot.weight * Uint128::from(tokens.len() as u128);
+                (tokens, weight)
+            }
+            _ => {
+                let (delegators, idle) =
+                    delegated_power(deps.as_ref(), &poll_id, &poll, &info.sender)?;
+                evict_idle_delegators(deps.storage, &info.sender, &idle)?;
+                ballot.delegators = delegators;
+                ballot.weight += ballot
+                    .delegators
+                    .iter()
+                    .map(|(_, power)| power)
+                    .sum::<Uint128>();
+                (vec![], ballot.weight)
+            }
+        };
+        if weight.is_zero() {
+            return Err(ContractError::NoVotingPower { poll_id });
+        }
 
-        poll.options[vote_position].1 += 1;
+        if let VotingPower::Cw721Tokens { .. } = poll.power {
+            for token_id in &tokens {
+                let old_ballot = TOKEN_BALLOTS.may_load(deps.storage, (&poll_id, token_id))?;
+                poll.replace_ballot(old_ballot.as_ref(), &ballot);
+                TOKEN_BALLOTS.save(deps.storage, (&poll_id, token_id), &ballot)?;
+            }
+        } else {
+            // a direct vote overrides the delegation that carried the voter's power so far
+            release_proxy(deps.storage, &mut poll, &poll_id, &info.sender)?;
+
+            let old_ballot = BALLOTS.may_load(deps.storage, (&poll_id, &info.sender))?;
+            for (delegator, _) in old_ballot.iter().flat_map(|old| &old.delegators) {
+                PROXIES.remove(deps.storage, (delegator, &poll_id));
+            }
+            for (delegator, _) in &ballot.delegators {
+                // the delegator may have moved on from a delegate who already voted
+                release_proxy(deps.storage, &mut poll, &poll_id, delegator)?;
+                PROXIES.save(deps.storage, (delegator, &poll_id), &info.sender)?;
+            }
+            poll.replace_ballot(old_ballot.as_ref(), &ballot);
+            BALLOTS.save(deps.storage, (&poll_id, &info.sender), &ballot)?;
+        }
         POLLS.save(deps.storage, &poll_id, &poll)?;
 
         Ok(Response::new()
             .add_attribute("action", "vote")
             .add_attribute("poll_id", poll_id)
             .add_attribute("voter", info.sender.to_string())
-            .add_attribute("vote", vote))
+            .add_attribute("vote", vote.selections().join(", "))
+            .add_attribute("weight", weight))
+    }
+
+    /// Stores a secret poll's commitment, tallies only change once it is revealed. Only voters
+    /// who could reveal with some power commit, so `PollResult.unrevealed` counts real voters
+    fn commit_vote(
+        deps: DepsMut,
+        info: MessageInfo,
+        poll_id: String,
+        poll: &Poll,
+        vote: Choice,
+    ) -> Result<Response, ContractError> {
+        let Choice::Commitment(commitment) = vote else {
+            return Err(ContractError::InvalidVote {});
+        };
+        if voting_power(deps.as_ref(), &poll_id, poll, &info.sender)?.is_zero() {
+            return Err(ContractError::NoVotingPower { poll_id });
+        }
+
+        let ballot = Ballot {
+            choice: Choice::Commitment(commitment.clone()),
+            weight: Uint128::zero(),
+            delegators: vec![],
+        };
+        BALLOTS.save(deps.storage, (&poll_id, &info.sender), &ballot)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "commit_vote")
+            .add_attribute("poll_id", poll_id)
+            .add_attribute("voter", info.sender.to_string())
+            .add_attribute("commitment", commitment.to_hex()))
+    }
+
+    pub fn execute_reveal(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        poll_id: String,
+        vote: Choice,
+        salt: String,
+    ) -> Result<Response, ContractError> {
+        let mut poll =
+            POLLS
+                .may_load(deps.storage, &poll_id)?
+                .ok_or(ContractError::PollNotFound {
+                    poll_id: poll_id.clone(),
+                })?;
+
+        // cancelling deactivates a poll just like its end does, but it is never counted
+        if poll.cancelled.is_some() {
+            return Err(ContractError::PollCancelled { poll_id });
+        }
+        let in_reveal_phase = poll
+            .reveal_end
+            .is_some_and(|end| poll.has_ended(&env.block) && !end.is_expired(&env.block));
+        if !in_reveal_phase || RESULTS.has(deps.storage, &poll_id) {
+            return Err(ContractError::NotRevealPhase { poll_id });
+        }
+
+        let commitment = match BALLOTS.may_load(deps.storage, (&poll_id, &info.sender))? {
+            Some(Ballot {
+                choice: Choice::Commitment(commitment),
+                ..
+            }) => commitment,
+            _ => return Err(ContractError::NoCommitment { poll_id }),
+        };
+
+        let mut hasher = Sha256::new();
+        hasher.update(to_json_vec(&vote)?);
+        hasher.update(salt.as_bytes());
+        if hasher.finalize().as_slice() != commitment.as_slice() {
+            return Err(ContractError::CommitmentMismatch {});
+        }
+
+        validate_choice(&poll, &vote)?;
+        let ballot = Ballot {
+            choice: vote.clone(),
+            weight: voting_power(deps.as_ref(), &poll_id, &poll, &info.sender)?,
+            delegators: vec![],
+        };
+        if ballot.weight.is_zero() {
+            return Err(ContractError::NoVotingPower { poll_id });
+        }
+
+        // the commitment was never counted, so this is the voter's first ballot
+        poll.replace_ballot(None, &ballot);
+        BALLOTS.save(deps.storage, (&poll_id, &info.sender), &ballot)?;
+        POLLS.save(deps.storage, &poll_id, &poll)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "reveal")
+            .add_attribute("poll_id", poll_id)
+            .add_attribute("voter", info.sender.to_string())
+            .add_attribute("vote", vote.selections().join(", "))
+            .add_attribute("weight", ballot.weight))
     }
 
     pub fn execute_close_poll(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         poll_id: String,
     ) -> Result<Response, ContractError> {
@@ -177,214 +583,4167 @@ pub mod execute {
         if info.sender != poll.creator && info.sender != CONFIG.load(deps.storage)?.admin {
             return Err(ContractError::Unauthorized {});
         }
+        // a cancelled poll's fee is forfeited, closing it must not pay it back
+        if poll.cancelled.is_some() {
+            return Err(ContractError::PollCancelled { poll_id });
+        }
+        if !poll.is_active {
+            return Err(ContractError::AlreadyClosed { poll_id });
+        }
 
-        let config = CONFIG.load(deps.storage)?;
         poll.is_active = false;
+        let runoff_poll = open_runoff(deps.storage, &env, &poll_id, &mut poll)?;
         POLLS.save(deps.storage, &poll_id, &poll)?;
+        let refund_msg = release_escrow(deps.storage, &poll_id, &poll.creator)?;
 
-        let bank_msg = BankMsg::Send {
-            to_address: poll.creator.to_string(),
-            amount: vec![config.fee],
+        let mut res = Response::new()
+            .add_messages(refund_msg)
+            .add_attribute("action", "close_poll")
+            .add_attribute("poll_id", poll_id);
+        if let Some(runoff_poll) = runoff_poll {
+            res = res.add_attribute("runoff_poll", runoff_poll);
+        }
+        Ok(res)
+    }
+
+    /// Takes a poll's fee out of escrow and pays it to `recipient`, the creator when it is
+    /// refunded. Nothing is paid for polls whose fee already left escrow, or that never held one
+    /// like runoff polls
+    fn release_escrow(
+        storage: &mut dyn Storage,
+        poll_id: &str,
+        recipient: &Addr,
+    ) -> Result<Option<CosmosMsg>, ContractError> {
+        let Some(deposit) = ESCROWS.may_load(storage, poll_id)? else {
+            return Ok(None);
         };
+        ESCROWS.remove(storage, poll_id);
 
-        Ok(Response::new()
-            .add_message(bank_msg)
-            .add_attribute("action", "close_poll")
-            .add_attribute("poll_id", poll_id))
+        let payout_msg = match deposit {
+            Deposit::Native(fee) => BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![fee],
+            }
+            .into(),
+            Deposit::Cw20(fee) => fee.transfer_msg(recipient)?,
+        };
+        Ok(Some(payout_msg))
     }
-}
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetAllPolls {} => to_json_binary(&query::get_all_polls(deps)?),
-        QueryMsg::GetPoll { poll_id } => to_json_binary(&query::get_poll(deps, poll_id)?),
-        QueryMsg::GetUserVote { poll_id, user } => {
-            to_json_binary(&query::get_user_vote(deps, poll_id, user)?)
+    /// Opens the second round between the two leading options once a poll with a runoff rule
+    /// ends without any option reaching the runoff share. Returns the id of the new poll
+    fn open_runoff(
+        storage: &mut dyn Storage,
+        env: &Env,
+        poll_id: &str,
+        poll: &mut Poll,
+    ) -> Result<Option<String>, ContractError> {
+        let Some(runoff) = &poll.runoff else {
+            return Ok(None);
+        };
+        if poll.runoff_poll.is_some() || poll.total_weight.is_zero() {
+            return Ok(None);
         }
-    }
-}
 
-pub mod query {
-    use super::*;
+        // the sort is stable, options level on votes keep the order they were listed in
+        let mut leading = poll.options.clone();
+        leading.sort_by(|(_, a), (_, b)| b.cmp(a));
+        if Decimal::from_ratio(leading[0].1, poll.total_weight) >= runoff.share {
+            return Ok(None);
+        }
 
-    pub fn get_all_polls(deps: Deps) -> StdResult<GetAllPollsResponse> {
-        let polls: Vec<Poll> = POLLS
-            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-            .collect::<StdResult<Vec<_>>>()?
-            .into_iter()
-            .map(|(_, poll)| poll)
-            .collect();
-        Ok(GetAllPollsResponse { polls })
-    }
+        // user polls can not take the id, but closing must never depend on it being free
+        let runoff_id = format!("{poll_id}{RUNOFF_SUFFIX}");
+        if POLLS.has(storage, &runoff_id) {
+            return Ok(None);
+        }
+        let fee = CONFIG.load(storage)?.fee;
+        let second_round = Poll {
+            creator: poll.creator.clone(),
+            question: poll.question.clone(),
+            options: leading
+                .into_iter()
+                .take(2)
+                .map(|(option, _)| (option, Uint128::zero()))
+                .collect(),
+            is_active: true,
+            deposit: Deposit::Native(Coin::new(0u128, fee.denom)),
+            start: None,
+            end: Some(runoff.duration.after(&env.block)),
+            reveal_end: None,
+            method: poll.method.clone(),
+            power: poll.power.clone(),
+            quorum: poll.quorum.clone(),
+            threshold: poll.threshold.clone(),
+            ballots: 0,
+            total_weight: Uint128::zero(),
+            comparisons: vec![],
+            questions: vec![],
+            runoff: None,
+            runoff_poll: None,
+            runoff_of: Some(poll_id.to_string()),
+            actions: None,
+            executed: false,
+            cancelled: None,
+            vetoed: None,
+        };
+        POLLS.save(storage, &runoff_id, &second_round)?;
 
-    pub fn get_poll(deps: Deps, poll_id: String) -> StdResult<GetPollResponse> {
-        let poll = POLLS.load(deps.storage, &poll_id)?;
-        Ok(GetPollResponse { poll: Some(poll) })
+        poll.runoff_poll = Some(runoff_id.clone());
+        Ok(Some(runoff_id))
     }
 
-    pub fn get_user_vote(
-        deps: Deps,
+    pub fn execute_finalize_poll(
+        deps: DepsMut,
+        env: Env,
         poll_id: String,
-        user: Addr,
-    ) -> StdResult<GetUserVoteResponse> {
-        let vote = BALLOTS.load(deps.storage, (user, &poll_id)).ok();
-        Ok(GetUserVoteResponse { vote })
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::contract::{execute, instantiate};
-    use crate::error::ContractError;
-    use crate::msg::{ExecuteMsg, InstantiateMsg};
-    use cosmwasm_std::testing::{mock_dependencies, mock_env};
-    use cosmwasm_std::{attr, from_json, Addr, Coin, MessageInfo, Uint128};
-    // use crate::state::{POLLS};
+    ) -> Result<Response, ContractError> {
+        let mut poll =
+            POLLS
+                .may_load(deps.storage, &poll_id)?
+                .ok_or(ContractError::PollNotFound {
+                    poll_id: poll_id.clone(),
+                })?;
 
-    use super::*;
+        if poll.cancelled.is_some() {
+            return Err(ContractError::PollCancelled { poll_id });
+        }
+        if !poll.can_finalize(&env.block) {
+            return Err(ContractError::PollNotEnded { poll_id });
+        }
+        if RESULTS.has(deps.storage, &poll_id) {
+            return Err(ContractError::AlreadyFinalized { poll_id });
+        }
 
-    #[test]
-    fn test_instantiate() {
-        let mut deps = mock_dependencies();
-        let env = mock_env();
-        let sender = deps.api.addr_make("sender").to_string();
-        let admin = deps.api.addr_make("admin").to_string();
-        let info = MessageInfo {
-            sender: Addr::unchecked(sender.clone()),
-            funds: vec![Coin {
-                denom: "uatom".to_string(),
-                amount: Uint128::from(1000u128),
-            }],
-        };
+        // an expired poll may never have been closed, make sure nothing can change it anymore
+        // and hand its creator the fee that closing would have refunded
+        poll.is_active = false;
+        let runoff_poll = open_runoff(deps.storage, &env, &poll_id, &mut poll)?;
+        POLLS.save(deps.storage, &poll_id, &poll)?;
+        let refund_msg = release_escrow(deps.storage, &poll_id, &poll.creator)?;
 
-        // Test with no admin specified (should use sender as admin)
-        let msg = InstantiateMsg { admin: None };
-        let res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-        assert_eq!(
-            res.attributes,
-            vec![attr("method", "instantiate"), attr("admin", sender)]
-        );
+        let result = count_poll(deps.as_ref(), &env, &poll_id, &poll)?;
+        RESULTS.save(deps.storage, &poll_id, &result)?;
 
-        // Test with specific admin
-        let msg = InstantiateMsg {
-            admin: Some(admin.clone()),
-        };
-        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(
-            res.attributes,
-            vec![attr("method", "instantiate"), attr("admin", admin)]
-        );
+        let mut res = Response::new()
+            .add_messages(refund_msg)
+            .add_attribute("action", "finalize_poll")
+            .add_attribute("poll_id", poll_id)
+            .add_attribute("outcome", outcome_attribute(&result.outcome))
+            .add_attribute("decision", decision_attribute(&result.decision));
+        if let Some(runoff_poll) = runoff_poll {
+            res = res.add_attribute("runoff_poll", runoff_poll);
+        }
+        Ok(res)
     }
 
-    #[test]
-    fn test_execute_create_poll() {
-        let mut deps = mock_dependencies();
-        let env = mock_env();
-        let sender = deps.api.addr_make("sender").to_string();
-        let info = MessageInfo {
-            sender: Addr::unchecked(sender.clone()),
-            funds: vec![Coin {
-                denom: "uatom".to_string(),
-                amount: Uint128::from(1000u128),
-            }],
+    /// Counts a poll that is over into the result `FinalizePoll` records, so everything judging
+    /// whether a poll passed measures it the same way
+    fn count_poll(
+        deps: Deps,
+        env: &Env,
+        poll_id: &str,
+        poll: &Poll,
+    ) -> Result<PollResult, ContractError> {
+        let ballots = poll_ballots(deps, poll_id, poll)?;
+        let unrevealed = ballots
+            .iter()
+            .filter(|ballot| matches!(ballot.choice, Choice::Commitment(_)))
+            .count() as u64;
+
+        let (outcome, detail) = match poll.method {
+            VotingMethod::Survey {} => {
+                let questions: Vec<QuestionResult> = poll
+                    .questions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, question)| {
+                        let answers: Vec<(&Choice, Uint128)> = ballots
+                            .iter()
+                            .filter_map(|ballot| match &ballot.choice {
+                                Choice::Survey(answers) => Some((&answers[i], ballot.weight)),
+                                _ => None,
+                            })
+                            .collect();
+                        let (outcome, detail) = count_votes(
+                            &question.method,
+                            &question.options,
+                            &question.comparisons,
+                            &answers,
+                            poll.ballots,
+                            poll.total_weight,
+                        );
+                        QuestionResult {
+                            question: question.question.clone(),
+                            tallies: question.options.clone(),
+                            outcome,
+                            detail,
+                        }
+                    })
+                    .collect();
+                let outcome = if poll.ballots == 0 {
+                    Outcome::NoVotes {}
+                } else {
+                    Outcome::Surveyed {}
+                };
+                (outcome, Some(ResultDetail::Survey { questions }))
+            }
+            ref method => {
+                let answers: Vec<(&Choice, Uint128)> = ballots
+                    .iter()
+                    .map(|ballot| (&ballot.choice, ballot.weight))
+                    .collect();
+                count_votes(
+                    method,
+                    &poll.options,
+                    &poll.comparisons,
+                    &answers,
+                    poll.ballots,
+                    poll.total_weight,
+                )
+            }
         };
 
-        let insufficient_funds_info = MessageInfo {
-            sender: Addr::unchecked(sender.clone()),
-            funds: vec![Coin {
-                denom: "uatom".to_string(),
-                amount: Uint128::from(100u128),
-            }],
+        // thresholds measure the winner's support in the last runoff round, if there was one,
+        // and a Schulze winner's support against the rival it is weakest against head to head
+        let final_tallies = match (&detail, &outcome) {
+            (Some(ResultDetail::InstantRunoff { rounds }), _) => {
+                rounds[rounds.len() - 1].tallies.clone()
+            }
+            (Some(ResultDetail::Schulze { pairwise, .. }), Outcome::Winner { option }) => {
+                let winner = poll
+                    .options
+                    .iter()
+                    .position(|(name, _)| name == option)
+                    .expect("winner is an option");
+                let weakest = pairwise[winner]
+                    .iter()
+                    .enumerate()
+                    .filter(|(rival, _)| *rival != winner)
+                    .map(|(_, support)| *support)
+                    .min()
+                    .unwrap_or_default();
+                vec![(option.clone(), weakest)]
+            }
+            _ => poll.options.clone(),
+        };
+        // quadratic, score and pairwise ballots cast several votes, so shares are taken of all
+        // votes cast
+        let votes_cast = match poll.method {
+            VotingMethod::Quadratic { .. }
+            | VotingMethod::Score { .. }
+            | VotingMethod::Pairwise {} => poll.options.iter().map(|(_, count)| count).sum(),
+            _ => poll.total_weight,
+        };
+        let decision = if poll.vetoed.is_some() {
+            Decision::Vetoed
+        } else {
+            tally::decide(
+                poll.quorum.as_ref(),
+                poll.threshold.as_ref(),
+                poll.ballots,
+                votes_cast,
+                &final_tallies,
+                &outcome,
+            )
         };
 
-        let msg = InstantiateMsg { admin: None };
-        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        Ok(PollResult {
+            tallies: poll.options.clone(),
+            outcome,
+            decision,
+            ballots: poll.ballots,
+            total_weight: poll.total_weight,
+            unrevealed,
+            detail,
+            finalized_height: env.block.height,
+        })
+    }
 
-        let valid_options = [("Option 1", 0), ("Option 2", 0), ("Option 3", 0)];
-        let invalid_options = [
-            ("Option 1", 0),
-            ("Option 2", 0),
-            ("Option 3", 0),
-            ("Option 4", 0),
-            ("Option 5", 0),
-            ("Option 6", 0),
-            ("Option 7", 0),
-            ("Option 8", 0),
-            ("Option 9", 0),
-            ("Option 10", 0),
+    /// Dispatches a poll's attached messages once it is over with their approving option leading
+    /// the tallies and meeting the poll's quorum and threshold
+    pub fn execute_poll_actions(
+        deps: DepsMut,
+        env: Env,
+        poll_id: String,
+    ) -> Result<Response, ContractError> {
+        let mut poll =
+            POLLS
+                .may_load(deps.storage, &poll_id)?
+                .ok_or(ContractError::PollNotFound {
+                    poll_id: poll_id.clone(),
+                })?;
+        let Some(actions) = poll.actions.clone() else {
+            return Err(ContractError::NoActions { poll_id });
+        };
+        if poll.cancelled.is_some() {
+            return Err(ContractError::PollCancelled { poll_id });
+        }
+        if poll.vetoed.is_some() {
+            return Err(ContractError::PollVetoed { poll_id });
+        }
+        if !poll.can_finalize(&env.block) {
+            return Err(ContractError::PollNotEnded { poll_id });
+        }
+        if poll.executed {
+            return Err(ContractError::AlreadyExecuted { poll_id });
+        }
+
+        let outcome = tally::plurality(&poll.options);
+        let decision = tally::decide(
+            poll.quorum.as_ref(),
+            poll.threshold.as_ref(),
+            poll.ballots,
+            poll.total_weight,
+            &poll.options,
+            &outcome,
+        );
+        let approved = matches!(&outcome, Outcome::Winner { option } if *option == actions.option);
+        if !approved || decision != Decision::Passed {
+            return Err(ContractError::NotPassed { poll_id });
+        }
+
+        poll.executed = true;
+        POLLS.save(deps.storage, &poll_id, &poll)?;
+
+        Ok(Response::new()
+            .add_messages(actions.msgs)
+            .add_attribute("action", "execute")
+            .add_attribute("poll_id", poll_id))
+    }
+
+    /// Ends an open poll without counting it, the creation fee is forfeited to the admin so
+    /// spam polls cost their creator
+    pub fn execute_cancel_poll(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        poll_id: String,
+        reason: String,
+    ) -> Result<Response, ContractError> {
+        let admin = CONFIG.load(deps.storage)?.admin;
+        if info.sender != admin {
+            return Err(ContractError::Unauthorized {});
+        }
+        let mut poll =
+            POLLS
+                .may_load(deps.storage, &poll_id)?
+                .ok_or(ContractError::PollNotFound {
+                    poll_id: poll_id.clone(),
+                })?;
+        if poll.cancelled.is_some() {
+            return Err(ContractError::PollCancelled { poll_id });
+        }
+        if poll.has_ended(&env.block) {
+            return Err(ContractError::PollClosed { poll_id });
+        }
+
+        poll.is_active = false;
+        poll.cancelled = Some(reason.clone());
+        POLLS.save(deps.storage, &poll_id, &poll)?;
+        let forfeit_msg = release_escrow(deps.storage, &poll_id, &admin)?;
+
+        Ok(Response::new()
+            .add_messages(forfeit_msg)
+            .add_attribute("action", "cancel_poll")
+            .add_attribute("poll_id", poll_id)
+            .add_attribute("reason", reason))
+    }
+
+    /// Overrules a poll that is over and passed, unless its messages already ran. The veto is
+    /// recorded as the decision of its result
+    pub fn execute_veto_poll(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        poll_id: String,
+        reason: String,
+    ) -> Result<Response, ContractError> {
+        if info.sender != CONFIG.load(deps.storage)?.admin {
+            return Err(ContractError::Unauthorized {});
+        }
+        let mut poll =
+            POLLS
+                .may_load(deps.storage, &poll_id)?
+                .ok_or(ContractError::PollNotFound {
+                    poll_id: poll_id.clone(),
+                })?;
+        if poll.cancelled.is_some() {
+            return Err(ContractError::PollCancelled { poll_id });
+        }
+        if poll.vetoed.is_some() {
+            return Err(ContractError::PollVetoed { poll_id });
+        }
+        if !poll.can_finalize(&env.block) {
+            return Err(ContractError::PollNotEnded { poll_id });
+        }
+        if poll.executed {
+            return Err(ContractError::AlreadyExecuted { poll_id });
+        }
+        // judge the poll on its recorded result, or count it the way finalizing will
+        let finalized = RESULTS.may_load(deps.storage, &poll_id)?;
+        let decision = match &finalized {
+            Some(result) => result.decision.clone(),
+            None => count_poll(deps.as_ref(), &env, &poll_id, &poll)?.decision,
+        };
+        if decision != Decision::Passed {
+            return Err(ContractError::NotPassed { poll_id });
+        }
+
+        poll.vetoed = Some(reason.clone());
+        POLLS.save(deps.storage, &poll_id, &poll)?;
+        if let Some(mut result) = finalized {
+            result.decision = Decision::Vetoed;
+            RESULTS.save(deps.storage, &poll_id, &result)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "veto_poll")
+            .add_attribute("poll_id", poll_id)
+            .add_attribute("reason", reason))
+    }
+
+    pub fn execute_set_paused(
+        deps: DepsMut,
+        info: MessageInfo,
+        paused: bool,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        if info.sender != config.admin {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        config.paused = paused;
+        CONFIG.save(deps.storage, &config)?;
+
+        let action = if paused { "pause" } else { "unpause" };
+        Ok(Response::new().add_attribute("action", action))
+    }
+
+    pub fn execute_delegate(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        delegate: Option<String>,
+        poll_id: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let poll = match &poll_id {
+            Some(poll_id) => Some(POLLS.may_load(deps.storage, poll_id)?.ok_or(
+                ContractError::PollNotFound {
+                    poll_id: poll_id.clone(),
+                },
+            )?),
+            None => None,
+        };
+        let scope = poll_id.as_deref().unwrap_or(GLOBAL_SCOPE);
+
+        let delegate = delegate
+            .map(|delegate| deps.api.addr_validate(&delegate))
+            .transpose()?;
+        if delegate.as_ref() == Some(&info.sender) {
+            return Err(ContractError::SelfDelegation {});
+        }
+
+        if let Some(old) = DELEGATIONS.may_load(deps.storage, (scope, &info.sender))? {
+            DELEGATORS.remove(deps.storage, (scope, &old, &info.sender));
+        }
+        match &delegate {
+            Some(delegate) => {
+                // a delegation for one poll must bring power to it, so it can not just hold a place
+                if let Some(poll) = &poll {
+                    if voting_power(deps.as_ref(), scope, poll, &info.sender)?.is_zero() {
+                        return Err(ContractError::NoVotingPower {
+                            poll_id: scope.to_string(),
+                        });
+                    }
+                }
+                let delegators = DELEGATORS
+                    .prefix((scope, delegate))
+                    .keys(deps.storage, None, None, Order::Ascending)
+                    .take(MAX_DELEGATORS as usize)
+                    .count();
+                if delegators >= MAX_DELEGATORS as usize {
+                    return Err(ContractError::TooManyDelegators {
+                        max: MAX_DELEGATORS,
+                    });
+                }
+                DELEGATIONS.save(deps.storage, (scope, &info.sender), delegate)?;
+                DELEGATORS.save(deps.storage, (scope, delegate, &info.sender), &Empty {})?;
+            }
+            None => DELEGATIONS.remove(deps.storage, (scope, &info.sender)),
+        }
+
+        // delegates who already voted with the sender's power keep it only while the sender
+        // still delegates to them, and only open polls change
+        let proxies: Vec<(String, Addr)> = match &poll_id {
+            Some(poll_id) => PROXIES
+                .may_load(deps.storage, (&info.sender, poll_id))?
+                .map(|proxy| (poll_id.clone(), proxy))
+                .into_iter()
+                .collect(),
+            None => PROXIES
+                .prefix(&info.sender)
+                .range(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<_>>()?,
+        };
+        for (proxied_poll, proxy) in proxies {
+            let current = match DELEGATIONS.may_load(deps.storage, (&proxied_poll, &info.sender))? {
+                Some(delegate) => Some(delegate),
+                None => DELEGATIONS.may_load(deps.storage, (GLOBAL_SCOPE, &info.sender))?,
+            };
+            if current.as_ref() == Some(&proxy) {
+                continue;
+            }
+            let mut poll = POLLS.load(deps.storage, &proxied_poll)?;
+            if poll.has_ended(&env.block) {
+                continue;
+            }
+            release_proxy(deps.storage, &mut poll, &proxied_poll, &info.sender)?;
+            POLLS.save(deps.storage, &proxied_poll, &poll)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "delegate")
+            .add_attribute("delegator", info.sender.to_string())
+            .add_attribute(
+                "delegate",
+                delegate.map_or("none".to_string(), |delegate| delegate.to_string()),
+            )
+            .add_attribute("poll_id", poll_id.unwrap_or("all".to_string())))
+    }
+
+    /// Outcome of one question under its voting method, from the answers given as
+    /// `(choice, weight)`, or from the stored `comparisons` of a pairwise question
+    pub fn count_votes(
+        method: &VotingMethod,
+        tallies: &[(String, Uint128)],
+        comparisons: &[(String, String, Uint128)],
+        answers: &[(&Choice, Uint128)],
+        ballots: u64,
+        total_weight: Uint128,
+    ) -> (Outcome, Option<ResultDetail>) {
+        let names: Vec<String> = tallies.iter().map(|(name, _)| name.clone()).collect();
+        let rankings = || -> Vec<(Vec<String>, Uint128)> {
+            answers
+                .iter()
+                .map(|(choice, weight)| {
+                    let ranking = choice.selections().into_iter().map(String::from);
+                    (ranking.collect(), *weight)
+                })
+                .collect()
+        };
+        match method {
+            VotingMethod::RankedChoice {} => {
+                let (outcome, rounds) = tally::instant_runoff(&names, &rankings());
+                (outcome, Some(ResultDetail::InstantRunoff { rounds }))
+            }
+            VotingMethod::Schulze {} => {
+                let (outcome, pairwise, strongest_paths) = tally::schulze(&names, &rankings());
+                let detail = ResultDetail::Schulze {
+                    pairwise,
+                    strongest_paths,
+                };
+                (outcome, Some(detail))
+            }
+            VotingMethod::Score { .. } => {
+                let scores = tally::scores(tallies, ballots, total_weight);
+                let detail = ResultDetail::Score { scores };
+                (tally::plurality(tallies), Some(detail))
+            }
+            VotingMethod::Estimate { quantiles, .. } => {
+                let estimates: Vec<(Decimal, Uint128)> = answers
+                    .iter()
+                    .filter_map(|(choice, weight)| match choice {
+                        Choice::Estimate(answer) => Some((*answer, *weight)),
+                        _ => None,
+                    })
+                    .collect();
+                match tally::estimate(&estimates, quantiles) {
+                    Some(summary) => (
+                        Outcome::Estimate {
+                            value: summary.median,
+                        },
+                        Some(ResultDetail::Estimate { summary }),
+                    ),
+                    None => (Outcome::NoVotes {}, None),
+                }
+            }
+            VotingMethod::Budgeting { budget, costs } => {
+                let (funded, spent) = tally::fund_projects(tallies, costs, *budget);
+                let outcome = if funded.is_empty() {
+                    Outcome::NoVotes {}
+                } else {
+                    Outcome::Elected {
+                        options: funded.iter().map(|(option, _)| option.clone()).collect(),
+                    }
+                };
+                (outcome, Some(ResultDetail::Budgeting { funded, spent }))
+            }
+            VotingMethod::SingleTransferable { seats } => {
+                let (outcome, quota, rounds) =
+                    tally::single_transferable_vote(&names, &rankings(), *seats);
+                let detail = ResultDetail::SingleTransferable { quota, rounds };
+                (outcome, Some(detail))
+            }
+            VotingMethod::Pairwise {} => {
+                let (outcome, ratings) = rate_options(&names, comparisons);
+                (outcome, Some(ResultDetail::Pairwise { ratings }))
+            }
+            _ => (tally::plurality(tallies), None),
+        }
+    }
+
+    /// Bradley-Terry fit of a pairwise poll's stored comparisons, which grow with the options
+    /// rather than with the ballots
+    pub fn rate_options(
+        options: &[String],
+        comparisons: &[(String, String, Uint128)],
+    ) -> (Outcome, Vec<(String, Decimal256)>) {
+        let comparisons: Vec<(&str, &str, Uint128)> = comparisons
+            .iter()
+            .map(|(winner, loser, weight)| (winner.as_str(), loser.as_str(), *weight))
+            .collect();
+        tally::bradley_terry(options, &comparisons)
+    }
+
+    fn outcome_attribute(outcome: &Outcome) -> String {
+        match outcome {
+            Outcome::Winner { option } => format!("winner: {option}"),
+            Outcome::Tie { options } => format!("tie: {}", options.join(", ")),
+            Outcome::Elected { options } => format!("elected: {}", options.join(", ")),
+            Outcome::Estimate { value } => format!("estimate: {value}"),
+            Outcome::Surveyed {} => "surveyed".to_string(),
+            Outcome::NoVotes {} => "no_votes".to_string(),
+        }
+    }
+
+    fn decision_attribute(decision: &Decision) -> &'static str {
+        match decision {
+            Decision::Passed => "passed",
+            Decision::Rejected => "rejected",
+            Decision::QuorumNotMet => "quorum_not_met",
+            Decision::Vetoed => "vetoed",
+        }
+    }
+
+    fn validate_rules(settings: &PollSettings) -> Result<(), ContractError> {
+        let valid_share = |share: &Decimal| !share.is_zero() && *share <= Decimal::one();
+
+        let quorum_valid = match &settings.quorum {
+            Some(Quorum::Share { share, electorate }) => valid_share(share) && *electorate > 0,
+            _ => true,
+        };
+        let threshold_valid = match &settings.threshold {
+            Some(Threshold::Supermajority { share }) => valid_share(share),
+            _ => true,
+        };
+        let runoff_valid = match &settings.runoff {
+            Some(runoff) => valid_share(&runoff.share),
+            None => true,
+        };
+        if !quorum_valid || !threshold_valid || !runoff_valid {
+            return Err(ContractError::InvalidRules {});
+        }
+
+        Ok(())
+    }
+
+    /// Weight the sender's ballot carries right now
+    fn voting_power(
+        deps: Deps,
+        poll_id: &str,
+        poll: &Poll,
+        voter: &Addr,
+    ) -> Result<Uint128, ContractError> {
+        match &poll.power {
+            VotingPower::Equal {} => Ok(Uint128::one()),
+            VotingPower::NativeBalance { denom } => {
+                Ok(deps.querier.query_balance(voter, denom)?.amount)
+            }
+            VotingPower::Cw20Balance { contract } => {
+                let res: BalanceResponse = deps.querier.query_wasm_smart(
+                    contract,
+                    &Cw20QueryMsg::Balance {
+                        address: voter.to_string(),
+                    },
+                )?;
+                Ok(res.balance)
+            }
+            // every token casts its own ballot, see `owned_tokens`
+            VotingPower::Cw721Tokens { .. } => Ok(Uint128::one()),
+            VotingPower::Cw4Group { contract } => {
+                let res: MemberResponse = deps.querier.query_wasm_smart(
+                    contract,
+                    &Cw4QueryMsg::Member {
+                        addr: voter.to_string(),
+                        at_height: None,
+                    },
+                )?;
+                let weight = res.weight.ok_or(ContractError::NotMember {
+                    poll_id: poll_id.to_string(),
+                })?;
+                Ok(weight.into())
+            }
+        }
+    }
+
+    /// Delegations that bring a delegate no power on a poll, as `(scope, delegator)`
+    type IdleDelegations<'a> = Vec<(&'a str, Addr)>;
+
+    /// Power handed to `delegate` for a poll by delegators who have not voted themselves, along
+    /// with the `(scope, delegator)` delegations that bring no power to the poll. A delegation
+    /// made for the poll takes precedence over the delegator's global one
+    fn delegated_power<'a>(
+        deps: Deps,
+        poll_id: &'a str,
+        poll: &Poll,
+        delegate: &Addr,
+    ) -> Result<(Vec<(Addr, Uint128)>, IdleDelegations<'a>), ContractError> {
+        let scoped = DELEGATORS
+            .prefix((poll_id, delegate))
+            .keys(deps.storage, None, None, Order::Ascending)
+            .map(|delegator| delegator.map(|delegator| (poll_id, delegator)));
+        let global = DELEGATORS
+            .prefix((GLOBAL_SCOPE, delegate))
+            .keys(deps.storage, None, None, Order::Ascending)
+            .filter(|delegator| {
+                delegator.as_ref().map_or(true, |delegator| {
+                    !DELEGATIONS.has(deps.storage, (poll_id, delegator))
+                })
+            })
+            .map(|delegator| delegator.map(|delegator| (GLOBAL_SCOPE, delegator)));
+
+        let mut delegators = vec![];
+        let mut idle = vec![];
+        for delegation in scoped.chain(global) {
+            let (scope, delegator) = delegation?;
+            if BALLOTS.has(deps.storage, (poll_id, &delegator)) {
+                continue;
+            }
+            let power = match voting_power(deps, poll_id, poll, &delegator) {
+                Ok(power) => power,
+                // outsiders of a group poll have nothing to hand on
+                Err(ContractError::NotMember { .. }) => Uint128::zero(),
+                Err(err) => return Err(err),
+            };
+            if power.is_zero() {
+                idle.push((scope, delegator));
+            } else {
+                delegators.push((delegator, power));
+            }
+        }
+        Ok((delegators, idle))
+    }
+
+    /// Drops the delegations that brought `delegate` no power, but only in scopes where the
+    /// delegate has no room left, so addresses without power can not keep a delegate full
+    fn evict_idle_delegators(
+        storage: &mut dyn Storage,
+        delegate: &Addr,
+        idle: &[(&str, Addr)],
+    ) -> StdResult<()> {
+        let mut full_scopes: Vec<&str> = idle.iter().map(|(scope, _)| *scope).collect();
+        full_scopes.dedup();
+        full_scopes.retain(|scope| {
+            DELEGATORS
+                .prefix((scope, delegate))
+                .keys(storage, None, None, Order::Ascending)
+                .take(MAX_DELEGATORS as usize)
+                .count()
+                >= MAX_DELEGATORS as usize
+        });
+
+        for (scope, delegator) in idle {
+            if full_scopes.contains(scope) {
+                DELEGATIONS.remove(storage, (scope, delegator));
+                DELEGATORS.remove(storage, (scope, delegate, delegator));
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes a delegator's power back out of the ballot of the delegate carrying it, if any
+    fn release_proxy(
+        storage: &mut dyn Storage,
+        poll: &mut Poll,
+        poll_id: &str,
+        delegator: &Addr,
+    ) -> StdResult<()> {
+        let Some(proxy) = PROXIES.may_load(storage, (delegator, poll_id))? else {
+            return Ok(());
+        };
+        PROXIES.remove(storage, (delegator, poll_id));
+
+        let mut ballot = BALLOTS.load(storage, (poll_id, &proxy))?;
+        poll.remove_votes(&ballot);
+        if let Some(i) = ballot
+            .delegators
+            .iter()
+            .position(|(addr, _)| addr == delegator)
+        {
+            let (_, power) = ballot.delegators.remove(i);
+            ballot.weight -= power;
+        }
+        // a delegate who voted on delegated power alone has nothing left to count
+        if ballot.weight.is_zero() {
+            for (delegator, _) in &ballot.delegators {
+                PROXIES.remove(storage, (delegator, poll_id));
+            }
+            BALLOTS.remove(storage, (poll_id, &proxy));
+            poll.ballots -= 1;
+            return Ok(());
+        }
+        poll.add_votes(&ballot);
+        BALLOTS.save(storage, (poll_id, &proxy), &ballot)
+    }
+
+    /// All token ids of the collection held by `owner`, paged through the enumerable extension
+    fn owned_tokens(deps: Deps, contract: &Addr, owner: &Addr) -> StdResult<Vec<String>> {
+        const PAGE: u32 = 30;
+
+        let mut tokens: Vec<String> = vec![];
+        loop {
+            let res: TokensResponse = deps.querier.query_wasm_smart(
+                contract,
+                &Cw721QueryMsg::Tokens {
+                    owner: owner.to_string(),
+                    start_after: tokens.last().cloned(),
+                    limit: Some(PAGE),
+                },
+            )?;
+            let done = res.tokens.len() < PAGE as usize;
+            tokens.extend(res.tokens);
+            if done {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    /// Every ballot cast in a poll, from whichever store its voting power keys them in
+    pub fn poll_ballots(deps: Deps, poll_id: &str, poll: &Poll) -> StdResult<Vec<Ballot>> {
+        match poll.power {
+            VotingPower::Cw721Tokens { .. } => TOKEN_BALLOTS
+                .prefix(poll_id)
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| item.map(|(_, ballot)| ballot))
+                .collect(),
+            _ => BALLOTS
+                .prefix(poll_id)
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| item.map(|(_, ballot)| ballot))
+                .collect(),
+        }
+    }
+
+    /// Checks every question of a new survey like the options and method of a poll
+    fn survey_questions(questions: Vec<Question>) -> Result<Vec<SurveyQuestion>, ContractError> {
+        if questions.is_empty() {
+            return Err(ContractError::InvalidMethod {});
+        }
+        if questions.len() > MAX_QUESTIONS {
+            return Err(ContractError::TooManyQuestions {});
+        }
+
+        questions
+            .into_iter()
+            .map(|question| {
+                if question.options.len() > MAX_OPTIONS {
+                    return Err(ContractError::TooManyOptions {});
+                }
+                let method = question.method.unwrap_or(VotingMethod::SingleChoice {});
+                validate_method(&method, question.options.len())?;
+                Ok(SurveyQuestion {
+                    question: question.question,
+                    options: question
+                        .options
+                        .into_iter()
+                        .map(|option| (option, Uint128::zero()))
+                        .collect(),
+                    method,
+                    comparisons: vec![],
+                })
+            })
+            .collect()
+    }
+
+    fn validate_method(method: &VotingMethod, options: usize) -> Result<(), ContractError> {
+        match method {
+            // surveys only come from `CreateSurvey` and do not nest
+            VotingMethod::Survey {} => Err(ContractError::InvalidMethod {}),
+            VotingMethod::SingleChoice {}
+            | VotingMethod::RankedChoice {}
+            | VotingMethod::Schulze {} => Ok(()),
+            VotingMethod::Pairwise {} if options < 2 => Err(ContractError::InvalidMethod {}),
+            VotingMethod::Pairwise {} => Ok(()),
+            VotingMethod::Approval { max_selections } => match max_selections {
+                Some(max) if *max == 0 || *max as usize > options => {
+                    Err(ContractError::InvalidMethod {})
+                }
+                _ => Ok(()),
+            },
+            VotingMethod::Quadratic { credits } => match credits {
+                0 => Err(ContractError::InvalidMethod {}),
+                _ => Ok(()),
+            },
+            VotingMethod::Score { min, max } if min >= max => Err(ContractError::InvalidMethod {}),
+            VotingMethod::SingleTransferable { seats }
+                if *seats == 0 || *seats as usize > options =>
+            {
+                Err(ContractError::InvalidMethod {})
+            }
+            VotingMethod::SingleTransferable { .. } => Ok(()),
+            VotingMethod::Estimate {
+                min,
+                max,
+                quantiles,
+            } => {
+                let valid_quantiles = quantiles.iter().all(|q| *q <= Decimal::one());
+                if min >= max || !valid_quantiles || options > 0 {
+                    return Err(ContractError::InvalidMethod {});
+                }
+                Ok(())
+            }
+            VotingMethod::Budgeting { budget, costs } => {
+                if budget.is_zero() || costs.len() != options {
+                    return Err(ContractError::InvalidMethod {});
+                }
+                Ok(())
+            }
+            VotingMethod::Score { .. } => Ok(()),
+        }
+    }
+
+    /// Checks a choice has the poll's shape, a survey's answers each the shape of their question
+    fn validate_choice(poll: &Poll, choice: &Choice) -> Result<(), ContractError> {
+        match (&poll.method, choice) {
+            (VotingMethod::Survey {}, Choice::Survey(answers)) => {
+                if answers.len() != poll.questions.len() {
+                    return Err(ContractError::InvalidVote {});
+                }
+                for (question, answer) in poll.questions.iter().zip(answers) {
+                    validate_answer(&question.method, &question.options, answer)?;
+                }
+                Ok(())
+            }
+            _ => validate_answer(&poll.method, &poll.options, choice),
+        }
+    }
+
+    /// Checks a choice fits the method and only names the options, each at most once
+    fn validate_answer(
+        method: &VotingMethod,
+        options: &[(String, Uint128)],
+        choice: &Choice,
+    ) -> Result<(), ContractError> {
+        match (method, choice) {
+            (VotingMethod::SingleChoice {}, Choice::Single(_)) => {}
+            (
+                VotingMethod::RankedChoice {}
+                | VotingMethod::Schulze {}
+                | VotingMethod::SingleTransferable { .. },
+                Choice::Ranked(ranking),
+            ) => {
+                if ranking.is_empty() {
+                    return Err(ContractError::InvalidVote {});
+                }
+            }
+            (VotingMethod::Estimate { min, max, .. }, Choice::Estimate(answer)) => {
+                if answer < min || answer > max {
+                    return Err(ContractError::EstimateOutOfRange {
+                        min: *min,
+                        max: *max,
+                    });
+                }
+            }
+            (VotingMethod::Pairwise {}, Choice::Pairwise(pairs)) => {
+                if pairs.is_empty() {
+                    return Err(ContractError::InvalidVote {});
+                }
+                if pairs.len() > MAX_COMPARISONS as usize {
+                    return Err(ContractError::TooManySelections {
+                        max: MAX_COMPARISONS,
+                    });
+                }
+                let known = |name: &String| options.iter().any(|(option, _)| option == name);
+                for (i, (winner, loser)) in pairs.iter().enumerate() {
+                    let repeated = pairs[..i]
+                        .iter()
+                        .any(|(a, b)| (a == winner && b == loser) || (a == loser && b == winner));
+                    if winner == loser || !known(winner) || !known(loser) || repeated {
+                        return Err(ContractError::InvalidVote {});
+                    }
+                }
+                // an option shows up in every comparison it is part of, so the check for
+                // repeated selections below does not apply
+                return Ok(());
+            }
+            (VotingMethod::Budgeting { .. }, Choice::Approval(selections)) => {
+                if selections.is_empty() {
+                    return Err(ContractError::InvalidVote {});
+                }
+            }
+            (VotingMethod::Approval { max_selections }, Choice::Approval(selections)) => {
+                if selections.is_empty() {
+                    return Err(ContractError::InvalidVote {});
+                }
+                if let Some(max) = max_selections {
+                    if selections.len() > *max as usize {
+                        return Err(ContractError::TooManySelections { max: *max });
+                    }
+                }
+            }
+            (VotingMethod::Quadratic { credits }, Choice::Quadratic(votes)) => {
+                if votes.is_empty() || votes.iter().any(|(_, votes)| *votes == 0) {
+                    return Err(ContractError::InvalidVote {});
+                }
+                if choice.credits().is_none_or(|spent| spent > *credits) {
+                    return Err(ContractError::OverBudget { credits: *credits });
+                }
+            }
+            (VotingMethod::Score { min, max }, Choice::Score(scores)) => {
+                // every option has to be scored, duplicates are caught below
+                if scores.len() != options.len() {
+                    return Err(ContractError::InvalidVote {});
+                }
+                if scores.iter().any(|(_, score)| score < min || score > max) {
+                    return Err(ContractError::ScoreOutOfRange {
+                        min: *min,
+                        max: *max,
+                    });
+                }
+            }
+            _ => return Err(ContractError::InvalidVote {}),
+        }
+
+        let selections = choice.selections();
+        for (i, selection) in selections.iter().enumerate() {
+            let known = options.iter().any(|(option, _)| option == selection);
+            if !known || selections[..i].contains(selection) {
+                return Err(ContractError::InvalidVote {});
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks attached messages can be approved by a plurality of the poll's options. The
+    /// contract holds the fees of every poll, so messages may only call other contracts and
+    /// never move funds, and the cw20 fee token is off limits
+    fn validate_actions(
+        deps: Deps,
+        env: &Env,
+        method: &VotingMethod,
+        options: &[String],
+        actions: &Actions,
+    ) -> Result<(), ContractError> {
+        let plurality = matches!(
+            method,
+            VotingMethod::SingleChoice {} | VotingMethod::Approval { .. }
+        );
+        if !plurality || actions.msgs.is_empty() || !options.contains(&actions.option) {
+            return Err(ContractError::InvalidMethod {});
+        }
+
+        let fee_token = CONFIG
+            .load(deps.storage)?
+            .cw20_fee
+            .map(|fee| fee.address.to_string());
+        for msg in &actions.msgs {
+            let allowed = match msg {
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr,
+                    funds,
+                    ..
+                }) => {
+                    funds.is_empty()
+                        && *contract_addr != env.contract.address.as_str()
+                        && Some(contract_addr) != fee_token.as_ref()
+                }
+                _ => false,
+            };
+            if !allowed {
+                return Err(ContractError::UnsupportedMessage {});
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rejects windows that are already over or that close before they open
+    fn validate_schedule(env: &Env, settings: &PollSettings) -> Result<(), ContractError> {
+        if let Some(end) = settings.end {
+            if end.is_expired(&env.block) {
+                return Err(ContractError::InvalidSchedule {});
+            }
+        }
+
+        let ends_before_start = match (settings.start, settings.end) {
+            (Some(Scheduled::AtHeight(start)), Some(Expiration::AtHeight(end))) => end <= start,
+            (Some(Scheduled::AtTime(start)), Some(Expiration::AtTime(end))) => end <= start,
+            _ => false,
+        };
+        if ends_before_start {
+            return Err(ContractError::InvalidSchedule {});
+        }
+
+        if let Some(reveal_end) = settings.reveal_end {
+            let reveals_after_end = settings
+                .end
+                .and_then(|end| reveal_end.partial_cmp(&end))
+                .is_some_and(|ordering| ordering.is_gt());
+            if !reveals_after_end {
+                return Err(ContractError::InvalidSchedule {});
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetAllPolls {} => to_json_binary(&query::get_all_polls(deps)?),
+        QueryMsg::GetPoll { poll_id } => to_json_binary(&query::get_poll(deps, poll_id)?),
+        QueryMsg::GetUserVote { poll_id, user } => {
+            to_json_binary(&query::get_user_vote(deps, poll_id, user)?)
+        }
+        QueryMsg::GetTokenVote { poll_id, token_id } => {
+            to_json_binary(&query::get_token_vote(deps, poll_id, token_id)?)
+        }
+        QueryMsg::GetPollResult { poll_id } => {
+            to_json_binary(&query::get_poll_result(deps, poll_id)?)
+        }
+        QueryMsg::GetFundedProjects { poll_id } => {
+            to_json_binary(&query::get_funded_projects(deps, poll_id)?)
+        }
+        QueryMsg::GetRanking { poll_id } => to_json_binary(&query::get_ranking(deps, poll_id)?),
+        QueryMsg::GetDelegation { delegator, poll_id } => {
+            to_json_binary(&query::get_delegation(deps, delegator, poll_id)?)
+        }
+    }
+}
+
+pub mod query {
+    use crate::state::{ResultDetail, VotingMethod};
+
+    use super::*;
+
+    pub fn get_all_polls(deps: Deps) -> StdResult<GetAllPollsResponse> {
+        let polls: Vec<Poll> = POLLS
+            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?
+            .into_iter()
+            .map(|(_, poll)| poll)
+            .collect();
+        Ok(GetAllPollsResponse { polls })
+    }
+
+    pub fn get_poll(deps: Deps, poll_id: String) -> StdResult<GetPollResponse> {
+        let poll = POLLS.load(deps.storage, &poll_id)?;
+        Ok(GetPollResponse { poll: Some(poll) })
+    }
+
+    pub fn get_user_vote(
+        deps: Deps,
+        poll_id: String,
+        user: Addr,
+    ) -> StdResult<GetUserVoteResponse> {
+        let vote = BALLOTS.load(deps.storage, (&poll_id, &user)).ok();
+        Ok(GetUserVoteResponse { vote })
+    }
+
+    pub fn get_token_vote(
+        deps: Deps,
+        poll_id: String,
+        token_id: String,
+    ) -> StdResult<GetTokenVoteResponse> {
+        let vote = TOKEN_BALLOTS.may_load(deps.storage, (&poll_id, &token_id))?;
+        Ok(GetTokenVoteResponse { vote })
+    }
+
+    pub fn get_poll_result(deps: Deps, poll_id: String) -> StdResult<GetPollResultResponse> {
+        let result = RESULTS.may_load(deps.storage, &poll_id)?;
+        Ok(GetPollResultResponse { result })
+    }
+
+    pub fn get_funded_projects(
+        deps: Deps,
+        poll_id: String,
+    ) -> StdResult<GetFundedProjectsResponse> {
+        let funded = RESULTS
+            .may_load(deps.storage, &poll_id)?
+            .and_then(|result| match result.detail {
+                Some(ResultDetail::Budgeting { funded, .. }) => Some(funded),
+                _ => None,
+            });
+        Ok(GetFundedProjectsResponse { funded })
+    }
+
+    /// Fits the ratings to the comparisons stored so far, so the ranking follows the poll as it
+    /// runs without reading its ballots
+    pub fn get_ranking(deps: Deps, poll_id: String) -> StdResult<GetRankingResponse> {
+        let poll = POLLS.load(deps.storage, &poll_id)?;
+        if poll.method != (VotingMethod::Pairwise {}) {
+            return Ok(GetRankingResponse { ranking: None });
+        }
+
+        let names: Vec<String> = poll.options.iter().map(|(name, _)| name.clone()).collect();
+        let (_, ratings) = execute::rate_options(&names, &poll.comparisons);
+        Ok(GetRankingResponse {
+            ranking: Some(ratings),
+        })
+    }
+
+    pub fn get_delegation(
+        deps: Deps,
+        delegator: Addr,
+        poll_id: Option<String>,
+    ) -> StdResult<GetDelegationResponse> {
+        let scoped = match &poll_id {
+            Some(poll_id) => DELEGATIONS.may_load(deps.storage, (poll_id, &delegator))?,
+            None => None,
+        };
+        let delegate = match scoped {
+            Some(delegate) => Some(delegate),
+            None => DELEGATIONS.may_load(deps.storage, (GLOBAL_SCOPE, &delegator))?,
+        };
+        Ok(GetDelegationResponse { delegate })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contract::{execute, instantiate};
+    use crate::cw20::{Cw20Coin, Cw20ExecuteMsg};
+    use crate::cw4::{Cw4QueryMsg, MemberResponse};
+    use crate::cw721::{Cw721QueryMsg, TokensResponse};
+    use crate::error::ContractError;
+    use crate::msg::{ExecuteMsg, InstantiateMsg, Question};
+    use crate::state::{
+        Actions, Choice, Decision, OptionScore, Outcome, Quorum, ResultDetail, Runoff, Threshold,
+        VotingMethod, VotingPower,
+    };
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{
+        attr, from_json, Addr, Coin, ContractResult, HexBinary, MessageInfo, SystemError,
+        SystemResult, Uint128, WasmMsg, WasmQuery,
+    };
+    use cosmwasm_std::{Decimal, Decimal256};
+    use cw_utils::{Duration, PaymentError};
+
+    use super::*;
+
+    #[test]
+    fn test_instantiate() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let admin = deps.api.addr_make("admin").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        // Test with no admin specified (should use sender as admin)
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("method", "instantiate"), attr("admin", sender)]
+        );
+
+        // Test with specific admin
+        let msg = InstantiateMsg {
+            admin: Some(admin.clone()),
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("method", "instantiate"), attr("admin", admin)]
+        );
+    }
+
+    #[test]
+    fn test_execute_create_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let insufficient_funds_info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(100u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let valid_options = [("Option 1", 0), ("Option 2", 0), ("Option 3", 0)];
+        let invalid_options = [
+            ("Option 1", 0),
+            ("Option 2", 0),
+            ("Option 3", 0),
+            ("Option 4", 0),
+            ("Option 5", 0),
+            ("Option 6", 0),
+            ("Option 7", 0),
+            ("Option 8", 0),
+            ("Option 9", 0),
+            ("Option 10", 0),
             ("Option 11", 0),
         ];
 
-        let question = "What is the best color?";
-        let poll_id = "poll1";
+        let question = "What is the best color?";
+        let poll_id = "poll1";
+
+        // create poll with invalid length of options
+        let create_poll_msg_invalid_len_options = ExecuteMsg::CreatePoll {
+            poll_id: poll_id.to_string(),
+            question: question.to_string(),
+            options: invalid_options
+                .iter()
+                .map(|(option, _)| option.to_string())
+                .collect(),
+            settings: PollSettings::default(),
+        };
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            create_poll_msg_invalid_len_options,
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::TooManyOptions {});
+
+        // create poll with insufficient funds
+        let create_poll_msg_invalid_len_options = ExecuteMsg::CreatePoll {
+            poll_id: poll_id.to_string(),
+            question: question.to_string(),
+            options: valid_options
+                .iter()
+                .map(|(option, _)| option.to_string())
+                .collect(),
+            settings: PollSettings::default(),
+        };
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            insufficient_funds_info,
+            create_poll_msg_invalid_len_options,
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::InsufficientFunds {});
+
+        // create poll without the fee paid exactly in the fee denom
+        for (funds, error) in [
+            (vec![], PaymentError::NoFunds {}.into()),
+            (
+                vec![Coin::new(1000u128, "ujuno")],
+                PaymentError::MissingDenom("uatom".to_string()).into(),
+            ),
+            (
+                vec![Coin::new(1000u128, "uatom"), Coin::new(1u128, "ujuno")],
+                PaymentError::MultipleDenoms {}.into(),
+            ),
+            (
+                vec![Coin::new(1500u128, "uatom")],
+                ContractError::Overpaid {
+                    paid: Uint128::new(1500),
+                    fee: Uint128::new(1000),
+                },
+            ),
+        ] {
+            let create_poll_msg = ExecuteMsg::CreatePoll {
+                poll_id: poll_id.to_string(),
+                question: question.to_string(),
+                options: vec!["Option 1".to_string(), "Option 2".to_string()],
+                settings: PollSettings::default(),
+            };
+            let info = MessageInfo {
+                sender: Addr::unchecked(sender.clone()),
+                funds,
+            };
+            let res = execute(deps.as_mut(), env.clone(), info, create_poll_msg).unwrap_err();
+            assert_eq!(res, error);
+        }
+
+        // create poll with valid
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: poll_id.to_string(),
+            question: question.to_string(),
+            options: valid_options
+                .iter()
+                .map(|(option, _)| option.to_string())
+                .collect(),
+            settings: PollSettings::default(),
+        };
+
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "create_poll"),
+                attr("poll_id", poll_id),
+                attr("creator", sender),
+                attr("question", question),
+                attr("options", "Option 1, Option 2, Option 3"),
+                attr("is_active", "true")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_execute_vote_invalid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let options = [("Option 1", 0), ("Option 2", 0), ("Option 3", 0)];
+        let question = "What is the best color?";
+        let poll_id = "poll1";
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: poll_id.to_string(),
+            question: question.to_string(),
+            options: options
+                .iter()
+                .map(|(option, _)| option.to_string())
+                .collect(),
+            settings: PollSettings::default(),
+        };
+
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        let invalid_vote_msg = ExecuteMsg::Vote {
+            poll_id: "poll2".to_string(),
+            vote: Choice::Single("Option 4".to_string()),
+        };
+
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), invalid_vote_msg).unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::PollNotFound {
+                poll_id: "poll2".to_string()
+            }
+        );
+
+        let invalid_vote2_msg = ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Single("Option 4".to_string()),
+        };
+
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), invalid_vote2_msg).unwrap_err();
+        assert_eq!(res, ContractError::InvalidVote {});
+    }
+
+    #[test]
+    fn test_execute_vote_valid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let options = [("Option 1", 0), ("Option 2", 0), ("Option 3", 0)];
+        let question = "What is the best color?";
+        let poll_id = "poll1";
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: poll_id.to_string(),
+            question: question.to_string(),
+            options: options
+                .iter()
+                .map(|(option, _)| option.to_string())
+                .collect(),
+            settings: PollSettings::default(),
+        };
+
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        let vote_msg = ExecuteMsg::Vote {
+            poll_id: poll_id.to_string(),
+            vote: Choice::Single("Option 1".to_string()),
+        };
+
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), vote_msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "vote"),
+                attr("poll_id", poll_id),
+                attr("voter", sender),
+                attr("vote", "Option 1"),
+                attr("weight", "1")
+            ]
+        );
+
+        // Verify the vote was counted
+        let poll = POLLS.load(deps.as_ref().storage, poll_id).unwrap();
+        assert_eq!(poll.options[0].1, Uint128::new(1)); // Option 1 should have 1 vote
+        assert_eq!(poll.options[1].1, Uint128::new(0)); // Option 2 should have 0 votes
+        assert_eq!(poll.options[2].1, Uint128::new(0)); // Option 3 should have 0 votes
+    }
+
+    #[test]
+    fn test_execute_vote_time_bounded() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let height = env.block.height;
+        let options = vec!["Option 1".to_string(), "Option 2".to_string()];
+
+        // end before start is rejected
+        let invalid_schedule_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "What is the best color?".to_string(),
+            options: options.clone(),
+            settings: PollSettings {
+                start: Some(Scheduled::AtHeight(height + 10)),
+                end: Some(Expiration::AtHeight(height + 10)),
+                ..Default::default()
+            },
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            invalid_schedule_msg,
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::InvalidSchedule {});
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "What is the best color?".to_string(),
+            options,
+            settings: PollSettings {
+                start: Some(Scheduled::AtHeight(height + 10)),
+                end: Some(Expiration::AtHeight(height + 20)),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        let vote_msg = ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Single("Option 1".to_string()),
+        };
+
+        // before the start height
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), vote_msg.clone()).unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::PollNotStarted {
+                poll_id: "poll1".to_string()
+            }
+        );
+
+        // inside the window
+        env.block.height = height + 10;
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), vote_msg.clone()).unwrap();
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(poll.options[0].1, Uint128::new(1));
+
+        // at the end height the poll is over without anyone closing it
+        env.block.height = height + 20;
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), vote_msg).unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::PollClosed {
+                poll_id: "poll1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_execute_finalize_poll() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let voter = deps.api.addr_make("voter").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+        let anyone_info = MessageInfo {
+            sender: Addr::unchecked("anyone".to_string()),
+            funds: vec![],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let options = vec!["Option 1".to_string(), "Option 2".to_string()];
+        let create_poll1_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "What is the best color?".to_string(),
+            options: options.clone(),
+            settings: PollSettings::default(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll1_msg).unwrap();
+
+        let create_poll2_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll2".to_string(),
+            question: "What is the best color?".to_string(),
+            options,
+            settings: PollSettings {
+                start: None,
+                end: Some(Expiration::AtHeight(env.block.height + 5)),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll2_msg).unwrap();
+
+        for (voter, poll_id, vote) in [
+            (&sender, "poll1", "Option 1"),
+            (&voter, "poll1", "Option 1"),
+            (&sender, "poll2", "Option 1"),
+            (&voter, "poll2", "Option 2"),
+        ] {
+            let vote_msg = ExecuteMsg::Vote {
+                poll_id: poll_id.to_string(),
+                vote: Choice::Single(vote.to_string()),
+            };
+            let voter_info = MessageInfo {
+                sender: Addr::unchecked(voter.clone()),
+                funds: vec![],
+            };
+            let _res = execute(deps.as_mut(), env.clone(), voter_info, vote_msg).unwrap();
+        }
+
+        // open polls can not be finalized
+        let finalize_poll1_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            anyone_info.clone(),
+            finalize_poll1_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::PollNotEnded {
+                poll_id: "poll1".to_string()
+            }
+        );
+
+        // once closed anyone can finalize
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            anyone_info.clone(),
+            finalize_poll1_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "finalize_poll"),
+                attr("poll_id", "poll1"),
+                attr("outcome", "winner: Option 1"),
+                attr("decision", "passed")
+            ]
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            anyone_info.clone(),
+            finalize_poll1_msg,
+        )
+        .unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::AlreadyFinalized {
+                poll_id: "poll1".to_string()
+            }
+        );
+
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        let result = result.result.unwrap();
+        assert_eq!(
+            result.outcome,
+            Outcome::Winner {
+                option: "Option 1".to_string()
+            }
+        );
+        assert_eq!(
+            result.tallies,
+            vec![
+                ("Option 1".to_string(), Uint128::new(2)),
+                ("Option 2".to_string(), Uint128::new(0))
+            ]
+        );
+
+        // an expired poll is finalized without being closed
+        env.block.height += 5;
+        let finalize_poll2_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll2".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), anyone_info, finalize_poll2_msg).unwrap();
+
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "poll2".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        assert_eq!(
+            result.result.unwrap().outcome,
+            Outcome::Tie {
+                options: vec!["Option 1".to_string(), "Option 2".to_string()]
+            }
+        );
+        let poll = POLLS.load(deps.as_ref().storage, "poll2").unwrap();
+        assert!(!poll.is_active);
+    }
+
+    #[test]
+    fn test_finalize_poll_quorum_and_threshold() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let invalid_rules_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll0".to_string(),
+            question: "Should we ship it?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            settings: PollSettings {
+                threshold: Some(Threshold::Supermajority {
+                    share: Decimal::percent(120),
+                }),
+                ..Default::default()
+            },
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), invalid_rules_msg).unwrap_err();
+        assert_eq!(res, ContractError::InvalidRules {});
+
+        let polls = [
+            // 2 of 5 voters is below a 50% quorum
+            (
+                "poll1",
+                Some(Quorum::Share {
+                    share: Decimal::percent(50),
+                    electorate: 5,
+                }),
+                None,
+                Decision::QuorumNotMet,
+            ),
+            // "Yes" has 2 of 3 votes, enough for a two-thirds supermajority
+            (
+                "poll2",
+                Some(Quorum::Ballots { count: 3 }),
+                Some(Threshold::Supermajority {
+                    share: Decimal::from_ratio(2u128, 3u128),
+                }),
+                Decision::Passed,
+            ),
+            // 2 votes fall short of an absolute count of 3
+            (
+                "poll3",
+                None,
+                Some(Threshold::AbsoluteCount {
+                    count: Uint128::new(3),
+                }),
+                Decision::Rejected,
+            ),
+        ];
+
+        for (poll_id, quorum, threshold, expected) in polls {
+            let create_poll_msg = ExecuteMsg::CreatePoll {
+                poll_id: poll_id.to_string(),
+                question: "Should we ship it?".to_string(),
+                options: vec!["Yes".to_string(), "No".to_string()],
+                settings: PollSettings {
+                    quorum,
+                    threshold,
+                    ..Default::default()
+                },
+            };
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+            let votes: &[(&str, &str)] = if poll_id == "poll1" {
+                &[("voter1", "Yes"), ("voter2", "Yes")]
+            } else {
+                &[("voter1", "Yes"), ("voter2", "Yes"), ("voter3", "No")]
+            };
+            for (voter, vote) in votes {
+                let vote_msg = ExecuteMsg::Vote {
+                    poll_id: poll_id.to_string(),
+                    vote: Choice::Single(vote.to_string()),
+                };
+                let voter_info = MessageInfo {
+                    sender: deps.api.addr_make(voter),
+                    funds: vec![],
+                };
+                let _res = execute(deps.as_mut(), env.clone(), voter_info, vote_msg).unwrap();
+            }
+
+            let close_poll_msg = ExecuteMsg::ClosePoll {
+                poll_id: poll_id.to_string(),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+            let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+                poll_id: poll_id.to_string(),
+            };
+            let _res =
+                execute(deps.as_mut(), env.clone(), info.clone(), finalize_poll_msg).unwrap();
+
+            let query_msg = QueryMsg::GetPollResult {
+                poll_id: poll_id.to_string(),
+            };
+            let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+            let result: GetPollResultResponse = from_json(&res).unwrap();
+            let result = result.result.unwrap();
+            assert_eq!(result.decision, expected, "{poll_id}");
+            assert_eq!(result.ballots, votes.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_execute_vote_approval() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "Which days work for you?".to_string(),
+            options: vec!["Mon".to_string(), "Tue".to_string(), "Wed".to_string()],
+            settings: PollSettings {
+                method: Some(VotingMethod::Approval {
+                    max_selections: Some(2),
+                }),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        let approve = |days: &[&str]| ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Approval(days.iter().map(|day| day.to_string()).collect()),
+        };
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            approve(&["Mon", "Tue", "Wed"]),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::TooManySelections { max: 2 });
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            approve(&["Mon", "Mon"]),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::InvalidVote {});
+
+        // single choice ballots do not fit an approval poll
+        let single_vote_msg = ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Single("Mon".to_string()),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), single_vote_msg).unwrap_err();
+        assert_eq!(res, ContractError::InvalidVote {});
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            approve(&["Mon", "Tue"]),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[3], attr("vote", "Mon, Tue"));
+
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(
+            poll.options,
+            vec![
+                ("Mon".to_string(), Uint128::new(1)),
+                ("Tue".to_string(), Uint128::new(1)),
+                ("Wed".to_string(), Uint128::new(0))
+            ]
+        );
+
+        // changing the ballot takes back every previous selection
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            approve(&["Tue", "Wed"]),
+        )
+        .unwrap();
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(
+            poll.options,
+            vec![
+                ("Mon".to_string(), Uint128::new(0)),
+                ("Tue".to_string(), Uint128::new(1)),
+                ("Wed".to_string(), Uint128::new(1))
+            ]
+        );
+        assert_eq!(poll.ballots, 1);
+    }
+
+    #[test]
+    fn test_execute_vote_quadratic() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "What should we fund first?".to_string(),
+            options: vec!["Docs".to_string(), "Audit".to_string(), "UI".to_string()],
+            settings: PollSettings {
+                method: Some(VotingMethod::Quadratic { credits: 10 }),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        let allocate = |votes: &[(&str, u64)]| ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Quadratic(
+                votes
+                    .iter()
+                    .map(|(option, votes)| (option.to_string(), *votes))
+                    .collect(),
+            ),
+        };
+
+        // 9 + 4 credits is over the budget of 10
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            allocate(&[("Audit", 3), ("Docs", 2)]),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::OverBudget { credits: 10 });
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            allocate(&[("Audit", 3), ("Audit", 1)]),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::InvalidVote {});
+
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            allocate(&[("Audit", 3), ("Docs", 1)]),
+        )
+        .unwrap();
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(
+            poll.options,
+            vec![
+                ("Docs".to_string(), Uint128::new(1)),
+                ("Audit".to_string(), Uint128::new(3)),
+                ("UI".to_string(), Uint128::new(0))
+            ]
+        );
+
+        // the budget is enforced again on a changed ballot, which replaces the old allocation
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            allocate(&[("UI", 2), ("Docs", 2), ("Audit", 1)]),
+        )
+        .unwrap();
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(
+            poll.options,
+            vec![
+                ("Docs".to_string(), Uint128::new(2)),
+                ("Audit".to_string(), Uint128::new(1)),
+                ("UI".to_string(), Uint128::new(2))
+            ]
+        );
+        assert_eq!(poll.ballots, 1);
+    }
+
+    #[test]
+    fn test_finalize_ranked_choice_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "Which logo?".to_string(),
+            options: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            settings: PollSettings {
+                method: Some(VotingMethod::RankedChoice {}),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        for (voter, ranking) in [
+            ("voter1", vec!["A", "C"]),
+            ("voter2", vec!["A"]),
+            ("voter3", vec!["B", "C"]),
+            ("voter4", vec!["B"]),
+            ("voter5", vec!["C", "B"]),
+        ] {
+            let vote_msg = ExecuteMsg::Vote {
+                poll_id: "poll1".to_string(),
+                vote: Choice::Ranked(ranking.iter().map(|option| option.to_string()).collect()),
+            };
+            let voter_info = MessageInfo {
+                sender: deps.api.addr_make(voter),
+                funds: vec![],
+            };
+            let _res = execute(deps.as_mut(), env.clone(), voter_info, vote_msg).unwrap();
+        }
+
+        // the live tallies only count first preferences
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(
+            poll.options,
+            vec![
+                ("A".to_string(), Uint128::new(2)),
+                ("B".to_string(), Uint128::new(2)),
+                ("C".to_string(), Uint128::new(1))
+            ]
+        );
+
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), finalize_poll_msg).unwrap();
+
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        let result = result.result.unwrap();
+
+        // C goes out first and its ballot moves to B
+        assert_eq!(
+            result.outcome,
+            Outcome::Winner {
+                option: "B".to_string()
+            }
+        );
+        let Some(ResultDetail::InstantRunoff { rounds }) = result.detail else {
+            panic!("Expected instant-runoff rounds");
+        };
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].eliminated, vec!["C".to_string()]);
+        assert_eq!(
+            rounds[1].tallies,
+            vec![
+                ("A".to_string(), Uint128::new(2)),
+                ("B".to_string(), Uint128::new(3))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_finalize_schulze_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "Who joins the steering committee?".to_string(),
+            options: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            settings: PollSettings {
+                method: Some(VotingMethod::Schulze {}),
+                threshold: Some(Threshold::Majority {}),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        // the same ballots as the instant-runoff test, B is eliminated there but is the
+        // Condorcet winner here: it beats A 3 to 2 and C 3 to 2, a majority against either
+        // although only one voter ranks it first
+        for (voter, ranking) in [
+            ("voter1", vec!["A", "B", "C"]),
+            ("voter2", vec!["A", "B", "C"]),
+            ("voter3", vec!["B", "C", "A"]),
+            ("voter4", vec!["C", "B", "A"]),
+            ("voter5", vec!["C", "B", "A"]),
+        ] {
+            let vote_msg = ExecuteMsg::Vote {
+                poll_id: "poll1".to_string(),
+                vote: Choice::Ranked(ranking.iter().map(|option| option.to_string()).collect()),
+            };
+            let voter_info = MessageInfo {
+                sender: deps.api.addr_make(voter),
+                funds: vec![],
+            };
+            let _res = execute(deps.as_mut(), env.clone(), voter_info, vote_msg).unwrap();
+        }
+
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), finalize_poll_msg).unwrap();
+
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        let result = result.result.unwrap();
+
+        assert_eq!(
+            result.outcome,
+            Outcome::Winner {
+                option: "B".to_string()
+            }
+        );
+        let Some(ResultDetail::Schulze {
+            pairwise,
+            strongest_paths,
+        }) = result.detail
+        else {
+            panic!("Expected the Schulze matrices");
+        };
+        assert_eq!(pairwise[1][0], Uint128::new(3));
+        assert_eq!(pairwise[0][1], Uint128::new(2));
+        assert_eq!(strongest_paths[1][2], Uint128::new(3));
+        assert_eq!(result.decision, Decision::Passed);
+    }
+
+    #[test]
+    fn test_finalize_score_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "How do you rate the release?".to_string(),
+            options: vec!["Speed".to_string(), "Docs".to_string()],
+            settings: PollSettings {
+                method: Some(VotingMethod::Score { min: 0, max: 5 }),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        let rate = |scores: &[(&str, u64)]| ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Score(
+                scores
+                    .iter()
+                    .map(|(option, score)| (option.to_string(), *score))
+                    .collect(),
+            ),
+        };
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            rate(&[("Speed", 6), ("Docs", 1)]),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::ScoreOutOfRange { min: 0, max: 5 });
+
+        // every option needs a score
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            rate(&[("Speed", 4)]),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::InvalidVote {});
+
+        for (voter, scores) in [
+            ("voter1", [("Speed", 5), ("Docs", 2)]),
+            ("voter2", [("Speed", 4), ("Docs", 3)]),
+            ("voter3", [("Docs", 0), ("Speed", 3)]),
+        ] {
+            let voter_info = MessageInfo {
+                sender: deps.api.addr_make(voter),
+                funds: vec![],
+            };
+            let _res = execute(deps.as_mut(), env.clone(), voter_info, rate(&scores)).unwrap();
+        }
+
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), finalize_poll_msg).unwrap();
+
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        let result = result.result.unwrap();
+
+        assert_eq!(
+            result.outcome,
+            Outcome::Winner {
+                option: "Speed".to_string()
+            }
+        );
+        let Some(ResultDetail::Score { scores }) = result.detail else {
+            panic!("Expected option scores");
+        };
+        assert_eq!(
+            scores,
+            vec![
+                OptionScore {
+                    option: "Speed".to_string(),
+                    sum: Uint128::new(12),
+                    mean: Decimal::from_ratio(4u128, 1u128),
+                    votes: 3,
+                },
+                OptionScore {
+                    option: "Docs".to_string(),
+                    sum: Uint128::new(5),
+                    mean: Decimal::from_ratio(5u128, 3u128),
+                    votes: 3,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_finalize_single_transferable_vote_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let options = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let create_poll = |seats: u32| ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "Who fills the two council seats?".to_string(),
+            options: options.clone(),
+            settings: PollSettings {
+                method: Some(VotingMethod::SingleTransferable { seats }),
+                ..Default::default()
+            },
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll(4)).unwrap_err();
+        assert_eq!(res, ContractError::InvalidMethod {});
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll(2)).unwrap();
+
+        for (voter, ranking) in [
+            ("voter1", vec!["A", "C"]),
+            ("voter2", vec!["A", "C"]),
+            ("voter3", vec!["A", "C"]),
+            ("voter4", vec!["A", "C"]),
+            ("voter5", vec!["A", "C"]),
+            ("voter6", vec!["B"]),
+            ("voter7", vec!["B"]),
+            ("voter8", vec!["C", "B"]),
+        ] {
+            let vote_msg = ExecuteMsg::Vote {
+                poll_id: "poll1".to_string(),
+                vote: Choice::Ranked(ranking.iter().map(|option| option.to_string()).collect()),
+            };
+            let voter_info = MessageInfo {
+                sender: deps.api.addr_make(voter),
+                funds: vec![],
+            };
+            let _res = execute(deps.as_mut(), env.clone(), voter_info, vote_msg).unwrap();
+        }
+
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), finalize_poll_msg).unwrap();
+        assert_eq!(res.attributes[2], attr("outcome", "elected: A, C"));
+
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        let result = result.result.unwrap();
+
+        // A is elected with 5 votes against a quota of 3, its surplus of 2 lifts C past B
+        assert_eq!(result.decision, Decision::Passed);
+        let Some(ResultDetail::SingleTransferable { quota, rounds }) = result.detail else {
+            panic!("Expected single transferable vote rounds");
+        };
+        assert_eq!(quota, Uint128::new(3));
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].elected, vec!["A".to_string()]);
+        assert_eq!(rounds[1].elected, vec!["C".to_string()]);
+    }
+
+    #[test]
+    fn test_finalize_budgeting_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create_poll = |costs: Vec<u128>| ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "Which grants do we fund this quarter?".to_string(),
+            options: vec![
+                "Wallet".to_string(),
+                "Indexer".to_string(),
+                "Docs".to_string(),
+            ],
+            settings: PollSettings {
+                method: Some(VotingMethod::Budgeting {
+                    budget: Uint128::new(1000),
+                    costs: costs.into_iter().map(Uint128::new).collect(),
+                }),
+                ..Default::default()
+            },
+        };
+        // every option needs a cost
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            create_poll(vec![600, 500]),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::InvalidMethod {});
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            create_poll(vec![600, 500, 300]),
+        )
+        .unwrap();
+
+        for (voter, projects) in [
+            ("voter1", vec!["Wallet", "Docs"]),
+            ("voter2", vec!["Wallet", "Indexer"]),
+            ("voter3", vec!["Indexer", "Docs"]),
+            ("voter4", vec!["Wallet"]),
+        ] {
+            let vote_msg = ExecuteMsg::Vote {
+                poll_id: "poll1".to_string(),
+                vote: Choice::Approval(
+                    projects.iter().map(|project| project.to_string()).collect(),
+                ),
+            };
+            let voter_info = MessageInfo {
+                sender: deps.api.addr_make(voter),
+                funds: vec![],
+            };
+            let _res = execute(deps.as_mut(), env.clone(), voter_info, vote_msg).unwrap();
+        }
+
+        let query_msg = QueryMsg::GetFundedProjects {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg.clone()).unwrap();
+        let funded: GetFundedProjectsResponse = from_json(&res).unwrap();
+        assert_eq!(funded.funded, None);
+
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), finalize_poll_msg).unwrap();
+
+        // the wallet leads, the indexer is level with the docs but no longer fits
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let funded: GetFundedProjectsResponse = from_json(&res).unwrap();
+        assert_eq!(
+            funded.funded,
+            Some(vec![
+                ("Wallet".to_string(), Uint128::new(600)),
+                ("Docs".to_string(), Uint128::new(300))
+            ])
+        );
+    }
+
+    #[test]
+    fn test_finalize_estimate_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "Where will ATOM close the year?".to_string(),
+            options: vec![],
+            settings: PollSettings {
+                method: Some(VotingMethod::Estimate {
+                    min: Decimal::zero(),
+                    max: Decimal::percent(10000),
+                    quantiles: vec![Decimal::percent(10), Decimal::percent(90)],
+                }),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        let estimate = |answer: u64| ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Estimate(Decimal::percent(answer)),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), estimate(12000)).unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::EstimateOutOfRange {
+                min: Decimal::zero(),
+                max: Decimal::percent(10000)
+            }
+        );
+
+        for (voter, answer) in [
+            ("voter1", 800),
+            ("voter2", 1200),
+            ("voter3", 950),
+            ("voter4", 3050),
+        ] {
+            let voter_info = MessageInfo {
+                sender: deps.api.addr_make(voter),
+                funds: vec![],
+            };
+            let _res = execute(deps.as_mut(), env.clone(), voter_info, estimate(answer)).unwrap();
+        }
+
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), finalize_poll_msg).unwrap();
+        assert_eq!(res.attributes[2], attr("outcome", "estimate: 9.5"));
+
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        let result = result.result.unwrap();
+
+        let Some(ResultDetail::Estimate { summary }) = result.detail else {
+            panic!("Expected an estimate summary");
+        };
+        assert_eq!(summary.mean, Decimal::percent(1500));
+        assert_eq!(summary.median, Decimal::percent(950));
+        assert_eq!(
+            summary.quantiles,
+            vec![
+                (Decimal::percent(10), Decimal::percent(800)),
+                (Decimal::percent(90), Decimal::percent(3050))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_execute_poll_actions() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let treasury = deps.api.addr_make("treasury").to_string();
+        let create_poll = |poll_id: &str, msgs: Vec<CosmosMsg>| ExecuteMsg::CreatePoll {
+            poll_id: poll_id.to_string(),
+            question: "Release the grant?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            settings: PollSettings {
+                actions: Some(Actions {
+                    option: "Yes".to_string(),
+                    msgs,
+                }),
+                ..Default::default()
+            },
+        };
+
+        // the contract holds every poll's fee, attached messages can not move funds
+        let bank_msg = BankMsg::Send {
+            to_address: sender.clone(),
+            amount: vec![Coin::new(1000u128, "uatom")],
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            create_poll("poll1", vec![bank_msg.into()]),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::UnsupportedMessage {});
+
+        let grant_msg: CosmosMsg = WasmMsg::Execute {
+            contract_addr: treasury,
+            msg: to_json_binary(&"release").unwrap(),
+            funds: vec![],
+        }
+        .into();
+        for poll_id in ["poll1", "poll2"] {
+            let create_poll_msg = create_poll(poll_id, vec![grant_msg.clone()]);
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+        }
+
+        let execute_msg = |poll_id: &str| ExecuteMsg::Execute {
+            poll_id: poll_id.to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            execute_msg("poll1"),
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::PollNotEnded {
+                poll_id: "poll1".to_string()
+            }
+        );
+
+        for (voter, poll1, poll2) in [
+            ("voter1", "Yes", "No"),
+            ("voter2", "Yes", "No"),
+            ("voter3", "No", "Yes"),
+        ] {
+            let voter_info = MessageInfo {
+                sender: deps.api.addr_make(voter),
+                funds: vec![],
+            };
+            for (poll_id, option) in [("poll1", poll1), ("poll2", poll2)] {
+                let vote_msg = ExecuteMsg::Vote {
+                    poll_id: poll_id.to_string(),
+                    vote: Choice::Single(option.to_string()),
+                };
+                let _res =
+                    execute(deps.as_mut(), env.clone(), voter_info.clone(), vote_msg).unwrap();
+            }
+        }
+        for poll_id in ["poll1", "poll2"] {
+            let close_poll_msg = ExecuteMsg::ClosePoll {
+                poll_id: poll_id.to_string(),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        }
+
+        let anyone = MessageInfo {
+            sender: deps.api.addr_make("anyone"),
+            funds: vec![],
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            anyone.clone(),
+            execute_msg("poll1"),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, grant_msg);
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            anyone.clone(),
+            execute_msg("poll1"),
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::AlreadyExecuted {
+                poll_id: "poll1".to_string()
+            }
+        );
+        let res = execute(deps.as_mut(), env.clone(), anyone, execute_msg("poll2"));
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::NotPassed {
+                poll_id: "poll2".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_cancel_and_veto_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let admin = MessageInfo {
+            sender: deps.api.addr_make("admin"),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+        let creator = MessageInfo {
+            sender: deps.api.addr_make("creator"),
+            funds: admin.funds.clone(),
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+
+        let grant_msg: CosmosMsg = WasmMsg::Execute {
+            contract_addr: deps.api.addr_make("treasury").to_string(),
+            msg: to_json_binary(&"release").unwrap(),
+            funds: vec![],
+        }
+        .into();
+        for poll_id in ["spam", "grant", "quiet"] {
+            let create_poll_msg = ExecuteMsg::CreatePoll {
+                poll_id: poll_id.to_string(),
+                question: "Release the grant?".to_string(),
+                options: vec!["Yes".to_string(), "No".to_string()],
+                settings: PollSettings {
+                    actions: Some(Actions {
+                        option: "Yes".to_string(),
+                        msgs: vec![grant_msg.clone()],
+                    }),
+                    ..Default::default()
+                },
+            };
+            let _res =
+                execute(deps.as_mut(), env.clone(), creator.clone(), create_poll_msg).unwrap();
+        }
+
+        let cancel_msg = ExecuteMsg::Cancel {
+            poll_id: "spam".to_string(),
+            reason: "spam".to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            cancel_msg.clone(),
+        );
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), cancel_msg).unwrap();
+        assert_eq!(res.attributes[2], attr("reason", "spam"));
+
+        // the fee is forfeited to the admin, the creator can not get it back by closing the poll
+        let forfeit: CosmosMsg = BankMsg::Send {
+            to_address: admin.sender.to_string(),
+            amount: vec![Coin::new(1000u128, "uatom")],
+        }
+        .into();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, forfeit);
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "spam".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), creator.clone(), close_poll_msg);
+        let cancelled = ContractError::PollCancelled {
+            poll_id: "spam".to_string(),
+        };
+        assert_eq!(res.unwrap_err(), cancelled);
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "spam".to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            finalize_poll_msg,
+        );
+        assert_eq!(res.unwrap_err(), cancelled);
+
+        let query_msg = QueryMsg::GetPoll {
+            poll_id: "spam".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let poll: GetPollResponse = from_json(&res).unwrap();
+        let poll = poll.poll.unwrap();
+        assert!(!poll.is_active);
+        assert_eq!(poll.cancelled, Some("spam".to_string()));
+
+        let vote_msg = ExecuteMsg::Vote {
+            poll_id: "grant".to_string(),
+            vote: Choice::Single("Yes".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), creator.clone(), vote_msg).unwrap();
+
+        let veto_msg = ExecuteMsg::Veto {
+            poll_id: "grant".to_string(),
+            reason: "treasury audit pending".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), veto_msg.clone());
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::PollNotEnded {
+                poll_id: "grant".to_string()
+            }
+        );
+
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "grant".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), creator.clone(), close_poll_msg).unwrap();
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "grant".to_string(),
+        };
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            finalize_poll_msg,
+        )
+        .unwrap();
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), veto_msg).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "veto_poll"));
+
+        // the recorded result no longer reads as passed
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "grant".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        assert_eq!(result.result.unwrap().decision, Decision::Vetoed);
+
+        let execute_msg = ExecuteMsg::Execute {
+            poll_id: "grant".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), creator.clone(), execute_msg);
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::PollVetoed {
+                poll_id: "grant".to_string()
+            }
+        );
+
+        // a poll that never passed has nothing to veto, finalized or not
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "quiet".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), creator, close_poll_msg).unwrap();
+        let veto_msg = ExecuteMsg::Veto {
+            poll_id: "quiet".to_string(),
+            reason: "treasury audit pending".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), admin, veto_msg);
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::NotPassed {
+                poll_id: "quiet".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_veto_counts_like_finalize() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let admin = MessageInfo {
+            sender: deps.api.addr_make("admin"),
+            funds: vec![Coin::new(1000u128, "uatom")],
+        };
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+
+        for (poll_id, method) in [
+            ("quadratic", VotingMethod::Quadratic { credits: 9 }),
+            ("ranked", VotingMethod::RankedChoice {}),
+        ] {
+            let create_poll_msg = ExecuteMsg::CreatePoll {
+                poll_id: poll_id.to_string(),
+                question: "Which proposal should be funded?".to_string(),
+                options: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+                settings: PollSettings {
+                    method: Some(method),
+                    threshold: Some(Threshold::Majority {}),
+                    ..Default::default()
+                },
+            };
+            let _res = execute(deps.as_mut(), env.clone(), admin.clone(), create_poll_msg).unwrap();
+        }
+
+        // three votes on A outweigh the single ballot they came from
+        let vote_msg = ExecuteMsg::Vote {
+            poll_id: "quadratic".to_string(),
+            vote: Choice::Quadratic(vec![("A".to_string(), 3)]),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), vote_msg).unwrap();
+
+        // A and B are level on first preferences, A wins the instant runoff once C is out
+        for (voter, ranking) in [
+            ("voter1", vec!["A"]),
+            ("voter2", vec!["A"]),
+            ("voter3", vec!["B"]),
+            ("voter4", vec!["B"]),
+            ("voter5", vec!["C", "A"]),
+        ] {
+            let voter = MessageInfo {
+                sender: deps.api.addr_make(voter),
+                funds: vec![],
+            };
+            let vote_msg = ExecuteMsg::Vote {
+                poll_id: "ranked".to_string(),
+                vote: Choice::Ranked(ranking.into_iter().map(String::from).collect()),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), voter, vote_msg).unwrap();
+        }
+
+        for poll_id in ["quadratic", "ranked"] {
+            let close_poll_msg = ExecuteMsg::ClosePoll {
+                poll_id: poll_id.to_string(),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), admin.clone(), close_poll_msg).unwrap();
+        }
+
+        // the quadratic poll is vetoed before it is finalized, the ranked one after
+        let veto_msg = ExecuteMsg::Veto {
+            poll_id: "quadratic".to_string(),
+            reason: "budget frozen".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), veto_msg).unwrap();
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "ranked".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), finalize_poll_msg).unwrap();
+        assert_eq!(res.attributes[3], attr("decision", "passed"));
+        let veto_msg = ExecuteMsg::Veto {
+            poll_id: "ranked".to_string(),
+            reason: "budget frozen".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), veto_msg).unwrap();
+
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "quadratic".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), admin, finalize_poll_msg).unwrap();
+        assert_eq!(res.attributes[3], attr("decision", "vetoed"));
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "ranked".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        assert_eq!(result.result.unwrap().decision, Decision::Vetoed);
+    }
+
+    #[test]
+    fn test_pause_contract() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let admin = MessageInfo {
+            sender: deps.api.addr_make("admin"),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "What's your favorite color?".to_string(),
+            options: vec!["Red".to_string(), "Blue".to_string()],
+            settings: PollSettings::default(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), create_poll_msg).unwrap();
+
+        let voter = MessageInfo {
+            sender: deps.api.addr_make("voter"),
+            funds: vec![],
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            voter.clone(),
+            ExecuteMsg::Pause {},
+        );
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll2".to_string(),
+            question: "What's your favorite fruit?".to_string(),
+            options: vec!["Apple".to_string(), "Pear".to_string()],
+            settings: PollSettings::default(),
+        };
+        let vote_msg = ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Single("Red".to_string()),
+        };
+        let reveal_msg = ExecuteMsg::Reveal {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Single("Red".to_string()),
+            salt: "salt".to_string(),
+        };
+        let delegate_msg = ExecuteMsg::Delegate {
+            delegate: Some(admin.sender.to_string()),
+            poll_id: None,
+        };
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let execute_msg = ExecuteMsg::Execute {
+            poll_id: "poll1".to_string(),
+        };
+        // nothing that moves funds or changes tallies gets through
+        for (info, msg) in [
+            (admin.clone(), create_poll_msg),
+            (voter.clone(), vote_msg.clone()),
+            (voter.clone(), reveal_msg),
+            (voter.clone(), delegate_msg),
+            (admin.clone(), close_poll_msg),
+            (admin.clone(), finalize_poll_msg),
+            (admin.clone(), execute_msg),
+        ] {
+            let res = execute(deps.as_mut(), env.clone(), info, msg);
+            assert_eq!(res.unwrap_err(), ContractError::Paused {});
+        }
+
+        // queries keep working while paused
+        let query_msg = QueryMsg::GetPoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let poll: GetPollResponse = from_json(&res).unwrap();
+        assert!(poll.poll.unwrap().is_active);
+
+        let _res = execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::Unpause {}).unwrap();
+        let _res = execute(deps.as_mut(), env.clone(), voter, vote_msg).unwrap();
+    }
+
+    #[test]
+    fn test_fee_escrow_refunded_once() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let admin = MessageInfo {
+            sender: deps.api.addr_make("admin"),
+            funds: vec![Coin::new(1000u128, "uatom")],
+        };
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+
+        let creator = MessageInfo {
+            sender: deps.api.addr_make("creator"),
+            funds: vec![Coin::new(1000u128, "uatom")],
+        };
+        for poll_id in ["poll1", "poll2"] {
+            let create_poll_msg = ExecuteMsg::CreatePoll {
+                poll_id: poll_id.to_string(),
+                question: "What's your favorite color?".to_string(),
+                options: vec!["Red".to_string(), "Blue".to_string()],
+                settings: PollSettings {
+                    end: Some(Expiration::AtHeight(env.block.height + 10)),
+                    ..Default::default()
+                },
+            };
+            let _res =
+                execute(deps.as_mut(), env.clone(), creator.clone(), create_poll_msg).unwrap();
+        }
+
+        // taking over an existing id would replace its creator and escrow
+        let mallory = MessageInfo {
+            sender: deps.api.addr_make("mallory"),
+            funds: vec![Coin::new(1000u128, "uatom")],
+        };
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "What's your favorite fruit?".to_string(),
+            options: vec!["Apple".to_string(), "Pear".to_string()],
+            settings: PollSettings::default(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mallory, create_poll_msg).unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::PollExists {
+                poll_id: "poll1".to_string()
+            }
+        );
+
+        let refund = CosmosMsg::from(BankMsg::Send {
+            to_address: creator.sender.to_string(),
+            amount: vec![Coin::new(1000u128, "uatom")],
+        });
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            close_poll_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, refund);
+
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), close_poll_msg);
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::AlreadyClosed {
+                poll_id: "poll1".to_string()
+            }
+        );
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), finalize_poll_msg).unwrap();
+        assert!(res.messages.is_empty());
+
+        // a poll that expired without being closed pays its fee back when it is finalized
+        env.block.height += 10;
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll2".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), finalize_poll_msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, refund);
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll2".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), creator, close_poll_msg);
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::AlreadyClosed {
+                poll_id: "poll2".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_zero_fee_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = MessageInfo {
+            sender: deps.api.addr_make("creator"),
+            funds: vec![],
+        };
+
+        // nothing has to be attached to deploy a contract with free polls
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(0u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+
+        let create_poll = |poll_id: &str| ExecuteMsg::CreatePoll {
+            poll_id: poll_id.to_string(),
+            question: "What's your favorite color?".to_string(),
+            options: vec!["Red".to_string(), "Blue".to_string()],
+            settings: PollSettings::default(),
+        };
+        let paying = MessageInfo {
+            sender: creator.sender.clone(),
+            funds: vec![Coin::new(1000u128, "uatom")],
+        };
+        let res = execute(deps.as_mut(), env.clone(), paying, create_poll("poll1")).unwrap_err();
+        assert_eq!(res, ContractError::Payment(PaymentError::NonPayable {}));
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            create_poll("poll1"),
+        )
+        .unwrap();
+
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), creator, close_poll_msg).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    #[test]
+    fn test_pairwise_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // more options than any other kind of poll may have
+        let options: Vec<String> = (1..=12).map(|i| format!("Logo {i}")).collect();
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "Which logo should we use?".to_string(),
+            options: options.clone(),
+            settings: PollSettings {
+                method: Some(VotingMethod::Pairwise {}),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        let compare = |pairs: &[(&str, &str)]| ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Pairwise(
+                pairs
+                    .iter()
+                    .map(|(winner, loser)| (winner.to_string(), loser.to_string()))
+                    .collect(),
+            ),
+        };
+        for pairs in [
+            vec![("Logo 1", "Logo 1")],
+            vec![("Logo 1", "Logo 2"), ("Logo 2", "Logo 1")],
+            vec![("Logo 1", "Logo 13")],
+        ] {
+            let res = execute(deps.as_mut(), env.clone(), info.clone(), compare(&pairs));
+            assert_eq!(res.unwrap_err(), ContractError::InvalidVote {});
+        }
+
+        // voter3 changes their mind, taking back the first comparison
+        for (voter, pairs) in [
+            ("voter3", vec![("Logo 2", "Logo 4")]),
+            ("voter1", vec![("Logo 3", "Logo 1"), ("Logo 3", "Logo 2")]),
+            ("voter2", vec![("Logo 3", "Logo 1"), ("Logo 1", "Logo 2")]),
+            ("voter3", vec![("Logo 1", "Logo 2"), ("Logo 1", "Logo 3")]),
+        ] {
+            let voter_info = MessageInfo {
+                sender: deps.api.addr_make(voter),
+                funds: vec![],
+            };
+            let _res = execute(deps.as_mut(), env.clone(), voter_info, compare(&pairs)).unwrap();
+        }
+
+        // the poll keeps the weight of every compared pair, the ranking is fit from it
+        let query_msg = QueryMsg::GetPoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let poll: GetPollResponse = from_json(&res).unwrap();
+        let comparisons: Vec<(&str, &str, u128)> = poll
+            .poll
+            .as_ref()
+            .unwrap()
+            .comparisons
+            .iter()
+            .map(|(winner, loser, weight)| (winner.as_str(), loser.as_str(), weight.u128()))
+            .collect();
+        assert_eq!(
+            comparisons,
+            vec![
+                ("Logo 3", "Logo 1", 2),
+                ("Logo 3", "Logo 2", 1),
+                ("Logo 1", "Logo 2", 2),
+                ("Logo 1", "Logo 3", 1),
+            ]
+        );
+
+        let query_msg = QueryMsg::GetRanking {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let ranking: GetRankingResponse = from_json(&res).unwrap();
+        let ranking = ranking.ranking.unwrap();
+        assert_eq!(ranking.len(), 12);
+        let top: Vec<&str> = ranking
+            .iter()
+            .take(4)
+            .map(|(option, _)| option.as_str())
+            .collect();
+        assert_eq!(top, vec!["Logo 3", "Logo 1", "Logo 4", "Logo 5"]);
+        // logos nobody compared stay at the starting strength of one
+        assert_eq!(ranking[2].1, Decimal256::one());
+        assert_eq!(ranking[11].0, "Logo 2");
+
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), finalize_poll_msg).unwrap();
+        assert_eq!(res.attributes[2], attr("outcome", "winner: Logo 3"));
+
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        let Some(ResultDetail::Pairwise { ratings }) = result.result.unwrap().detail else {
+            panic!("Expected the pairwise ratings");
+        };
+        assert_eq!(ratings, ranking);
+    }
+
+    #[test]
+    fn test_runoff_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "Who should chair the council?".to_string(),
+            options: vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()],
+            settings: PollSettings {
+                runoff: Some(Runoff {
+                    share: Decimal::percent(50),
+                    duration: Duration::Height(100),
+                }),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        // taking the runoff's id first would block the first round from closing
+        let squat_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1-runoff".to_string(),
+            question: "Who should chair the council?".to_string(),
+            options: vec!["Mallory".to_string()],
+            settings: PollSettings::default(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), squat_msg).unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::ReservedPollId {
+                poll_id: "poll1-runoff".to_string()
+            }
+        );
+
+        let vote = |poll_id: &str, option: &str| ExecuteMsg::Vote {
+            poll_id: poll_id.to_string(),
+            vote: Choice::Single(option.to_string()),
+        };
+        for (voter, option) in [
+            ("voter1", "Bob"),
+            ("voter2", "Alice"),
+            ("voter3", "Bob"),
+            ("voter4", "Alice"),
+            ("voter5", "Carol"),
+        ] {
+            let voter_info = MessageInfo {
+                sender: deps.api.addr_make(voter),
+                funds: vec![],
+            };
+            let _res = execute(
+                deps.as_mut(),
+                env.clone(),
+                voter_info,
+                vote("poll1", option),
+            )
+            .unwrap();
+        }
+
+        // no option holds half the weight, the two leading options go to a second round and
+        // the tie between them keeps the order they were listed in
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.attributes[2], attr("runoff_poll", "poll1-runoff"));
+
+        let query_msg = QueryMsg::GetPoll {
+            poll_id: "poll1-runoff".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let runoff: GetPollResponse = from_json(&res).unwrap();
+        let runoff = runoff.poll.unwrap();
+        assert_eq!(
+            runoff.options,
+            vec![
+                ("Alice".to_string(), Uint128::zero()),
+                ("Bob".to_string(), Uint128::zero())
+            ]
+        );
+        assert_eq!(runoff.runoff_of, Some("poll1".to_string()));
+        assert_eq!(
+            runoff.end,
+            Some(Expiration::AtHeight(env.block.height + 100))
+        );
 
-        // create poll with invalid length of options
-        let create_poll_msg_invalid_len_options = ExecuteMsg::CreatePoll {
-            poll_id: poll_id.to_string(),
-            question: question.to_string(),
-            options: invalid_options
-                .iter()
-                .map(|(option, _)| option.to_string())
-                .collect(),
+        // finalizing the first round does not open another runoff
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), finalize_poll_msg).unwrap();
+        assert_eq!(res.attributes.len(), 4);
+
+        let voter_info = MessageInfo {
+            sender: deps.api.addr_make("voter5"),
+            funds: vec![],
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            voter_info.clone(),
+            vote("poll1-runoff", "Carol"),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::InvalidVote {});
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            voter_info,
+            vote("poll1-runoff", "Alice"),
+        )
+        .unwrap();
+
+        // the second round holds no fee, closing it refunds nothing
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1-runoff".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(res.attributes.len(), 2);
+    }
+
+    #[test]
+    fn test_survey_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create_survey_msg = ExecuteMsg::CreateSurvey {
+            poll_id: "survey1".to_string(),
+            title: "Quarterly community survey".to_string(),
+            questions: vec![
+                Question {
+                    question: "Which chain do you use most?".to_string(),
+                    options: vec!["Hub".to_string(), "Osmosis".to_string()],
+                    method: None,
+                },
+                Question {
+                    question: "Which channels do you follow?".to_string(),
+                    options: vec!["Forum".to_string(), "Discord".to_string(), "X".to_string()],
+                    method: Some(VotingMethod::Approval {
+                        max_selections: None,
+                    }),
+                },
+            ],
+            settings: PollSettings::default(),
         };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_survey_msg).unwrap();
 
+        let answer = |chain: &str, channels: &[&str]| ExecuteMsg::Vote {
+            poll_id: "survey1".to_string(),
+            vote: Choice::Survey(vec![
+                Choice::Single(chain.to_string()),
+                Choice::Approval(channels.iter().map(|channel| channel.to_string()).collect()),
+            ]),
+        };
+
+        // every question needs an answer of its own shape
+        let partial_msg = ExecuteMsg::Vote {
+            poll_id: "survey1".to_string(),
+            vote: Choice::Survey(vec![Choice::Single("Hub".to_string())]),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), partial_msg).unwrap_err();
+        assert_eq!(res, ContractError::InvalidVote {});
         let res = execute(
             deps.as_mut(),
             env.clone(),
             info.clone(),
-            create_poll_msg_invalid_len_options,
+            answer("Juno", &["Forum"]),
         )
         .unwrap_err();
-        assert_eq!(res, ContractError::TooManyOptions {});
+        assert_eq!(res, ContractError::InvalidVote {});
 
-        // create poll with insufficient funds
-        let create_poll_msg_invalid_len_options = ExecuteMsg::CreatePoll {
-            poll_id: poll_id.to_string(),
-            question: question.to_string(),
-            options: valid_options
+        for (voter, chain, channels) in [
+            ("voter1", "Hub", vec!["Forum", "X"]),
+            ("voter2", "Osmosis", vec!["Forum"]),
+            ("voter3", "Hub", vec!["Discord"]),
+        ] {
+            let voter_info = MessageInfo {
+                sender: deps.api.addr_make(voter),
+                funds: vec![],
+            };
+            let _res = execute(
+                deps.as_mut(),
+                env.clone(),
+                voter_info,
+                answer(chain, &channels),
+            )
+            .unwrap();
+        }
+
+        let poll = POLLS.load(deps.as_ref().storage, "survey1").unwrap();
+        assert_eq!(poll.ballots, 3);
+        assert_eq!(
+            poll.questions[1].options,
+            vec![
+                ("Forum".to_string(), Uint128::new(2)),
+                ("Discord".to_string(), Uint128::new(1)),
+                ("X".to_string(), Uint128::new(1))
+            ]
+        );
+
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "survey1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), close_poll_msg).unwrap();
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
+            poll_id: "survey1".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), finalize_poll_msg).unwrap();
+        assert_eq!(res.attributes[2], attr("outcome", "surveyed"));
+
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "survey1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        let Some(ResultDetail::Survey { questions }) = result.result.unwrap().detail else {
+            panic!("Expected per question results");
+        };
+        assert_eq!(
+            questions
                 .iter()
-                .map(|(option, _)| option.to_string())
-                .collect(),
+                .map(|question| question.outcome.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                Outcome::Winner {
+                    option: "Hub".to_string()
+                },
+                Outcome::Winner {
+                    option: "Forum".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_execute_vote_native_balance_weighted() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let voter = deps.api.addr_make("voter");
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+        let voter_info = MessageInfo {
+            sender: voter.clone(),
+            funds: vec![],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "Should the DAO fund it?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            settings: PollSettings {
+                power: Some(VotingPower::NativeBalance {
+                    denom: "ugov".to_string(),
+                }),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        let vote = |option: &str| ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Single(option.to_string()),
+        };
+
+        // holding none of the denom means no say
+        let res = execute(deps.as_mut(), env.clone(), voter_info.clone(), vote("Yes")).unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::NoVotingPower {
+                poll_id: "poll1".to_string()
+            }
+        );
+
+        deps.querier
+            .bank
+            .update_balance(voter.as_str(), vec![Coin::new(100u128, "ugov")]);
+        let res = execute(deps.as_mut(), env.clone(), voter_info.clone(), vote("Yes")).unwrap();
+        assert_eq!(res.attributes[4], attr("weight", "100"));
+
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(poll.options[0].1, Uint128::new(100));
+        assert_eq!(poll.total_weight, Uint128::new(100));
+
+        // the balance shrank, switching must take back the original 100 and add the new 40
+        deps.querier
+            .bank
+            .update_balance(voter.as_str(), vec![Coin::new(40u128, "ugov")]);
+        let _res = execute(deps.as_mut(), env.clone(), voter_info.clone(), vote("No")).unwrap();
+
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(poll.options[0].1, Uint128::zero());
+        assert_eq!(poll.options[1].1, Uint128::new(40));
+        assert_eq!(poll.total_weight, Uint128::new(40));
+
+        let ballot = BALLOTS
+            .load(deps.as_ref().storage, ("poll1", &voter))
+            .unwrap();
+        assert_eq!(ballot.weight, Uint128::new(40));
+    }
+
+    #[test]
+    fn test_cw20_fee_and_cw20_weighted_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let deployer = deps.api.addr_make("deployer");
+        let token = deps.api.addr_make("token");
+        let holder = deps.api.addr_make("holder");
+
+        let (token_addr, holder_addr) = (token.to_string(), holder.to_string());
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, msg } if *contract_addr == token_addr => {
+                let Cw20QueryMsg::Balance { address } = from_json(msg).unwrap();
+                let balance = if address == holder_addr { 250u128 } else { 0 };
+                let res = BalanceResponse {
+                    balance: Uint128::new(balance),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&res).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: Some(Cw20Coin {
+                address: token.to_string(),
+                amount: Uint128::new(50),
+            }),
+        };
+        let info = MessageInfo {
+            sender: deployer,
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let create_poll = |amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: holder.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_json_binary(&ReceiveMsg::CreatePoll {
+                    poll_id: "poll1".to_string(),
+                    question: "Should the DAO fund it?".to_string(),
+                    options: vec!["Yes".to_string(), "No".to_string()],
+                    settings: PollSettings {
+                        power: Some(VotingPower::Cw20Balance {
+                            contract: token.clone(),
+                        }),
+                        ..Default::default()
+                    },
+                })
+                .unwrap(),
+            })
+        };
+        let token_info = MessageInfo {
+            sender: token.clone(),
+            funds: vec![],
         };
 
+        // only the configured token can pay
+        let other_token_info = MessageInfo {
+            sender: deps.api.addr_make("other_token"),
+            funds: vec![],
+        };
         let res = execute(
             deps.as_mut(),
             env.clone(),
-            insufficient_funds_info,
-            create_poll_msg_invalid_len_options,
+            other_token_info,
+            create_poll(50),
+        )
+        .unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::UnsupportedToken {
+                address: deps.api.addr_make("other_token").to_string()
+            }
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            token_info.clone(),
+            create_poll(10),
         )
         .unwrap_err();
         assert_eq!(res, ContractError::InsufficientFunds {});
 
-        // create poll with valid
+        let res = execute(deps.as_mut(), env.clone(), token_info, create_poll(50)).unwrap();
+        assert_eq!(res.attributes[2], attr("creator", holder.to_string()));
+
+        // the holder's ballot weighs their token balance
+        let holder_info = MessageInfo {
+            sender: holder.clone(),
+            funds: vec![],
+        };
+        let vote_msg = ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Single("Yes".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), holder_info.clone(), vote_msg).unwrap();
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(poll.options[0].1, Uint128::new(250));
+
+        // closing hands the cw20 fee back rather than native coins
+        let close_poll_msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll1".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), holder_info, close_poll_msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: holder.to_string(),
+                    amount: Uint128::new(50),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn test_execute_vote_cw4_group_members_only() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let group = deps.api.addr_make("group");
+        let member = deps.api.addr_make("member");
+        let outsider = deps.api.addr_make("outsider");
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let (group_addr, member_addr) = (group.to_string(), member.to_string());
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, msg } if *contract_addr == group_addr => {
+                let Cw4QueryMsg::Member { addr, .. } = from_json(msg).unwrap();
+                let res = MemberResponse {
+                    weight: (addr == member_addr).then_some(3),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&res).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll1".to_string(),
+            question: "Adopt the new charter?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            settings: PollSettings {
+                power: Some(VotingPower::Cw4Group {
+                    contract: group.clone(),
+                }),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        let vote_msg = ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Single("Yes".to_string()),
+        };
+
+        let outsider_info = MessageInfo {
+            sender: outsider.clone(),
+            funds: vec![],
+        };
+        let res = execute(deps.as_mut(), env.clone(), outsider_info, vote_msg.clone()).unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::NotMember {
+                poll_id: "poll1".to_string()
+            }
+        );
+        assert!(!BALLOTS.has(deps.as_ref().storage, ("poll1", &outsider)));
+
+        let member_info = MessageInfo {
+            sender: member.clone(),
+            funds: vec![],
+        };
+        let _res = execute(deps.as_mut(), env.clone(), member_info.clone(), vote_msg).unwrap();
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(poll.options[0].1, Uint128::new(3));
+        assert_eq!(poll.ballots, 1);
+
+        // outsiders can not commit to a secret group poll either
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll2".to_string(),
+            question: "Adopt the new charter?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            settings: PollSettings {
+                end: Some(Expiration::AtHeight(env.block.height + 10)),
+                reveal_end: Some(Expiration::AtHeight(env.block.height + 20)),
+                power: Some(VotingPower::Cw4Group { contract: group }),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+        let commit_msg = ExecuteMsg::Vote {
+            poll_id: "poll2".to_string(),
+            vote: Choice::Commitment(HexBinary::from(&[7u8; 32])),
+        };
+        let outsider_info = MessageInfo {
+            sender: outsider.clone(),
+            funds: vec![],
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            outsider_info,
+            commit_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::NotMember {
+                poll_id: "poll2".to_string()
+            }
+        );
+        assert!(!BALLOTS.has(deps.as_ref().storage, ("poll2", &outsider)));
+        let _res = execute(deps.as_mut(), env.clone(), member_info, commit_msg).unwrap();
+        assert!(BALLOTS.has(deps.as_ref().storage, ("poll2", &member)));
+    }
+
+    #[test]
+    fn test_execute_vote_one_ballot_per_nft() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let sender = deps.api.addr_make("sender").to_string();
+        let collection = deps.api.addr_make("collection");
+        let alice = deps.api.addr_make("alice");
+        let bob = deps.api.addr_make("bob");
+        let info = MessageInfo {
+            sender: Addr::unchecked(sender.clone()),
+            funds: vec![Coin {
+                denom: "uatom".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        };
+
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
         let create_poll_msg = ExecuteMsg::CreatePoll {
-            poll_id: poll_id.to_string(),
-            question: question.to_string(),
-            options: valid_options
-                .iter()
-                .map(|(option, _)| option.to_string())
-                .collect(),
+            poll_id: "poll1".to_string(),
+            question: "Which trait next?".to_string(),
+            options: vec!["Hats".to_string(), "Capes".to_string()],
+            settings: PollSettings {
+                power: Some(VotingPower::Cw721Tokens {
+                    contract: collection.clone(),
+                }),
+                ..Default::default()
+            },
         };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
 
-        let res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+        let holdings = |owners: Vec<(Addr, Vec<&'static str>)>| {
+            let collection = collection.to_string();
+            move |query: &WasmQuery| match query {
+                WasmQuery::Smart { contract_addr, msg } if *contract_addr == collection => {
+                    let Cw721QueryMsg::Tokens { owner, .. } = from_json(msg).unwrap();
+                    let tokens = owners
+                        .iter()
+                        .find(|(holder, _)| holder.as_str() == owner)
+                        .map(|(_, tokens)| tokens.iter().map(|t| t.to_string()).collect())
+                        .unwrap_or_default();
+                    let res = TokensResponse { tokens };
+                    SystemResult::Ok(ContractResult::Ok(to_json_binary(&res).unwrap()))
+                }
+                _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                    kind: "wasm".to_string(),
+                }),
+            }
+        };
+        let vote = |option: &str| ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Single(option.to_string()),
+        };
+        let alice_info = MessageInfo {
+            sender: alice.clone(),
+            funds: vec![],
+        };
+        let bob_info = MessageInfo {
+            sender: bob.clone(),
+            funds: vec![],
+        };
+
+        deps.querier
+            .update_wasm(holdings(vec![(alice.clone(), vec!["token1", "token2"])]));
+
+        let res = execute(deps.as_mut(), env.clone(), bob_info.clone(), vote("Capes")).unwrap_err();
         assert_eq!(
-            res.attributes,
+            res,
+            ContractError::NoVotingPower {
+                poll_id: "poll1".to_string()
+            }
+        );
+
+        let res = execute(deps.as_mut(), env.clone(), alice_info, vote("Hats")).unwrap();
+        assert_eq!(res.attributes[4], attr("weight", "2"));
+
+        // token2 moves to bob, his vote replaces its ballot instead of adding a third
+        deps.querier.update_wasm(holdings(vec![
+            (alice.clone(), vec!["token1"]),
+            (bob.clone(), vec!["token2"]),
+        ]));
+        let _res = execute(deps.as_mut(), env.clone(), bob_info, vote("Capes")).unwrap();
+
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(
+            poll.options,
             vec![
-                attr("action", "create_poll"),
-                attr("poll_id", poll_id),
-                attr("creator", sender),
-                attr("question", question),
-                attr("options", "Option 1, Option 2, Option 3"),
-                attr("is_active", "true")
+                ("Hats".to_string(), Uint128::new(1)),
+                ("Capes".to_string(), Uint128::new(1))
             ]
         );
+        assert_eq!(poll.ballots, 2);
+
+        let query_msg = QueryMsg::GetTokenVote {
+            poll_id: "poll1".to_string(),
+            token_id: "token2".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let token_vote: GetTokenVoteResponse = from_json(&res).unwrap();
+        assert_eq!(
+            token_vote.vote.unwrap().choice,
+            Choice::Single("Capes".to_string())
+        );
     }
 
     #[test]
-    fn test_execute_vote_invalid() {
+    fn test_commit_reveal_poll() {
         let mut deps = mock_dependencies();
-        let env = mock_env();
+        let mut env = mock_env();
         let sender = deps.api.addr_make("sender").to_string();
         let info = MessageInfo {
             sender: Addr::unchecked(sender.clone()),
@@ -394,48 +4753,165 @@ mod tests {
             }],
         };
 
-        let msg = InstantiateMsg { admin: None };
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-        let options = [("Option 1", 0), ("Option 2", 0), ("Option 3", 0)];
-        let question = "What is the best color?";
-        let poll_id = "poll1";
-
+        let height = env.block.height;
         let create_poll_msg = ExecuteMsg::CreatePoll {
-            poll_id: poll_id.to_string(),
-            question: question.to_string(),
-            options: options
-                .iter()
-                .map(|(option, _)| option.to_string())
-                .collect(),
+            poll_id: "poll1".to_string(),
+            question: "Who should lead the working group?".to_string(),
+            options: vec!["Alice".to_string(), "Bob".to_string()],
+            settings: PollSettings {
+                end: Some(Expiration::AtHeight(height + 10)),
+                reveal_end: Some(Expiration::AtHeight(height + 20)),
+                ..Default::default()
+            },
         };
-
         let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
 
-        let invalid_vote_msg = ExecuteMsg::Vote {
-            poll_id: "poll2".to_string(),
-            vote: "Option 4".to_string(),
+        let commitment = |choice: &Choice, salt: &str| {
+            let mut hasher = Sha256::new();
+            hasher.update(to_json_vec(choice).unwrap());
+            hasher.update(salt.as_bytes());
+            HexBinary::from(hasher.finalize().as_slice())
+        };
+        let alice_vote = Choice::Single("Alice".to_string());
+        let voter1 = MessageInfo {
+            sender: deps.api.addr_make("voter1"),
+            funds: vec![],
+        };
+        let voter2 = MessageInfo {
+            sender: deps.api.addr_make("voter2"),
+            funds: vec![],
         };
 
-        let res = execute(deps.as_mut(), env.clone(), info.clone(), invalid_vote_msg).unwrap_err();
+        // plain votes are refused while the poll is secret
+        let plain_vote_msg = ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: alice_vote.clone(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), voter1.clone(), plain_vote_msg).unwrap_err();
+        assert_eq!(res, ContractError::InvalidVote {});
+
+        for (voter, salt) in [(&voter1, "salt1"), (&voter2, "salt2")] {
+            let commit_msg = ExecuteMsg::Vote {
+                poll_id: "poll1".to_string(),
+                vote: Choice::Commitment(commitment(&alice_vote, salt)),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), voter.clone(), commit_msg).unwrap();
+        }
+
+        // nothing is visible while committing
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(poll.options[0].1, Uint128::zero());
+        assert_eq!(poll.ballots, 0);
+
+        // a cancelled secret poll takes no reveals
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll2".to_string(),
+            question: "Who should lead the working group?".to_string(),
+            options: vec!["Alice".to_string(), "Bob".to_string()],
+            settings: PollSettings {
+                end: Some(Expiration::AtHeight(height + 10)),
+                reveal_end: Some(Expiration::AtHeight(height + 20)),
+                ..Default::default()
+            },
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+        let commit_msg = ExecuteMsg::Vote {
+            poll_id: "poll2".to_string(),
+            vote: Choice::Commitment(commitment(&alice_vote, "salt1")),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), voter1.clone(), commit_msg).unwrap();
+        let cancel_msg = ExecuteMsg::Cancel {
+            poll_id: "poll2".to_string(),
+            reason: "spam".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), cancel_msg).unwrap();
+        let reveal_cancelled_msg = ExecuteMsg::Reveal {
+            poll_id: "poll2".to_string(),
+            vote: alice_vote.clone(),
+            salt: "salt1".to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            voter1.clone(),
+            reveal_cancelled_msg,
+        )
+        .unwrap_err();
         assert_eq!(
             res,
-            ContractError::PollNotFound {
+            ContractError::PollCancelled {
                 poll_id: "poll2".to_string()
             }
         );
 
-        let invalid_vote2_msg = ExecuteMsg::Vote {
+        let reveal = |salt: &str| ExecuteMsg::Reveal {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Single("Alice".to_string()),
+            salt: salt.to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), voter1.clone(), reveal("salt1")).unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::NotRevealPhase {
+                poll_id: "poll1".to_string()
+            }
+        );
+
+        env.block.height = height + 10;
+        let res = execute(deps.as_mut(), env.clone(), voter1.clone(), reveal("salt2")).unwrap_err();
+        assert_eq!(res, ContractError::CommitmentMismatch {});
+
+        let res = execute(deps.as_mut(), env.clone(), voter1.clone(), reveal("salt1")).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "reveal"));
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(poll.options[0].1, Uint128::one());
+
+        let finalize_poll_msg = ExecuteMsg::FinalizePoll {
             poll_id: "poll1".to_string(),
-            vote: "Option 4".to_string(),
         };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            finalize_poll_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            res,
+            ContractError::PollNotEnded {
+                poll_id: "poll1".to_string()
+            }
+        );
 
-        let res = execute(deps.as_mut(), env.clone(), info.clone(), invalid_vote2_msg).unwrap_err();
-        assert_eq!(res, ContractError::InvalidVote {});
+        // voter2 never reveals
+        env.block.height = height + 20;
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), finalize_poll_msg).unwrap();
+
+        let query_msg = QueryMsg::GetPollResult {
+            poll_id: "poll1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let result: GetPollResultResponse = from_json(&res).unwrap();
+        let result = result.result.unwrap();
+        assert_eq!(result.ballots, 1);
+        assert_eq!(result.unrevealed, 1);
+        assert_eq!(
+            result.outcome,
+            Outcome::Winner {
+                option: "Alice".to_string()
+            }
+        );
     }
 
     #[test]
-    fn test_execute_vote_valid() {
+    fn test_delegated_voting() {
         let mut deps = mock_dependencies();
         let env = mock_env();
         let sender = deps.api.addr_make("sender").to_string();
@@ -447,45 +4923,276 @@ mod tests {
             }],
         };
 
-        let msg = InstantiateMsg { admin: None };
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-        let options = [("Option 1", 0), ("Option 2", 0), ("Option 3", 0)];
-        let question = "What is the best color?";
-        let poll_id = "poll1";
-
         let create_poll_msg = ExecuteMsg::CreatePoll {
-            poll_id: poll_id.to_string(),
-            question: question.to_string(),
-            options: options
-                .iter()
-                .map(|(option, _)| option.to_string())
-                .collect(),
+            poll_id: "poll1".to_string(),
+            question: "Should the DAO fund it?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            settings: PollSettings::default(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+
+        let delegate = deps.api.addr_make("delegate");
+        let delegate_info = MessageInfo {
+            sender: delegate.clone(),
+            funds: vec![],
+        };
+        let voter1 = MessageInfo {
+            sender: deps.api.addr_make("voter1"),
+            funds: vec![],
+        };
+        let voter2 = MessageInfo {
+            sender: deps.api.addr_make("voter2"),
+            funds: vec![],
+        };
+        let delegate_msg = |poll_id: Option<&str>| ExecuteMsg::Delegate {
+            delegate: Some(delegate.to_string()),
+            poll_id: poll_id.map(str::to_string),
+        };
+        let vote = |option: &str| ExecuteMsg::Vote {
+            poll_id: "poll1".to_string(),
+            vote: Choice::Single(option.to_string()),
+        };
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            delegate_info.clone(),
+            delegate_msg(None),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::SelfDelegation {});
+
+        // voter1 delegates for every poll, voter2 only for this one
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            voter1.clone(),
+            delegate_msg(None),
+        )
+        .unwrap();
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            voter2.clone(),
+            delegate_msg(Some("poll1")),
+        )
+        .unwrap();
+
+        let query_msg = QueryMsg::GetDelegation {
+            delegator: voter2.sender.clone(),
+            poll_id: Some("poll1".to_string()),
+        };
+        let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let delegation: GetDelegationResponse = from_json(&res).unwrap();
+        assert_eq!(delegation.delegate, Some(delegate.clone()));
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            delegate_info.clone(),
+            vote("Yes"),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[4], attr("weight", "3"));
+
+        // voter1's own vote takes their power back out of the delegate's ballot
+        let _res = execute(deps.as_mut(), env.clone(), voter1.clone(), vote("No")).unwrap();
+
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(poll.options[0].1, Uint128::new(2));
+        assert_eq!(poll.options[1].1, Uint128::one());
+        assert_eq!(poll.ballots, 2);
+        assert_eq!(poll.total_weight, Uint128::new(3));
+
+        let ballot = BALLOTS
+            .load(deps.as_ref().storage, ("poll1", &delegate))
+            .unwrap();
+        assert_eq!(ballot.weight, Uint128::new(2));
+        assert_eq!(
+            ballot.delegators,
+            vec![(voter2.sender.clone(), Uint128::one())]
+        );
+
+        // a changed vote by the delegate keeps carrying voter2 but not voter1
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            delegate_info.clone(),
+            vote("No"),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[4], attr("weight", "2"));
+
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(poll.options[0].1, Uint128::zero());
+        assert_eq!(poll.options[1].1, Uint128::new(3));
+
+        // withdrawing a delegation takes the power back out of the ballot that carried it
+        let undelegate_msg = ExecuteMsg::Delegate {
+            delegate: None,
+            poll_id: Some("poll1".to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), voter2, undelegate_msg).unwrap();
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(poll.options[1].1, Uint128::new(2));
+
+        // and so does moving it to someone who never votes
+        let voter3 = MessageInfo {
+            sender: deps.api.addr_make("voter3"),
+            funds: vec![],
+        };
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            voter3.clone(),
+            delegate_msg(None),
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            delegate_info.clone(),
+            vote("No"),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[4], attr("weight", "2"));
+
+        let redelegate_msg = ExecuteMsg::Delegate {
+            delegate: Some(deps.api.addr_make("absent").to_string()),
+            poll_id: None,
         };
+        let _res = execute(deps.as_mut(), env.clone(), voter3, redelegate_msg).unwrap();
+        let poll = POLLS.load(deps.as_ref().storage, "poll1").unwrap();
+        assert_eq!(poll.options[1].1, Uint128::new(2));
+        let ballot = BALLOTS
+            .load(deps.as_ref().storage, ("poll1", &delegate))
+            .unwrap();
+        assert_eq!(ballot.weight, Uint128::one());
+        assert!(ballot.delegators.is_empty());
 
+        // a delegate without power of their own loses their ballot with the last delegator
+        let create_poll_msg = ExecuteMsg::CreatePoll {
+            poll_id: "poll2".to_string(),
+            question: "Should the DAO fund it?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            settings: PollSettings {
+                power: Some(VotingPower::NativeBalance {
+                    denom: "ugov".to_string(),
+                }),
+                ..Default::default()
+            },
+        };
         let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
+        let broker = MessageInfo {
+            sender: deps.api.addr_make("broker"),
+            funds: vec![],
+        };
+        let holder = MessageInfo {
+            sender: deps.api.addr_make("holder"),
+            funds: vec![],
+        };
+        deps.querier
+            .bank
+            .update_balance(holder.sender.as_str(), vec![Coin::new(10u128, "ugov")]);
+        let delegate_to_broker = ExecuteMsg::Delegate {
+            delegate: Some(broker.sender.to_string()),
+            poll_id: Some("poll2".to_string()),
+        };
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            holder.clone(),
+            delegate_to_broker,
+        )
+        .unwrap();
+        let vote_poll2 = |option: &str| ExecuteMsg::Vote {
+            poll_id: "poll2".to_string(),
+            vote: Choice::Single(option.to_string()),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            broker.clone(),
+            vote_poll2("Yes"),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[4], attr("weight", "10"));
+        let _res = execute(deps.as_mut(), env.clone(), holder, vote_poll2("No")).unwrap();
 
-        let vote_msg = ExecuteMsg::Vote {
-            poll_id: poll_id.to_string(),
-            vote: "Option 1".to_string(),
+        let poll = POLLS.load(deps.as_ref().storage, "poll2").unwrap();
+        assert_eq!(poll.ballots, 1);
+        assert_eq!(poll.total_weight, Uint128::new(10));
+        assert_eq!(poll.options[0].1, Uint128::zero());
+        assert!(!BALLOTS.has(deps.as_ref().storage, ("poll2", &broker.sender)));
+
+        // a delegate only takes so many delegators, whose power is queried on every vote;
+        // voter1 still holds one of the 50 global places
+        for i in 0..49 {
+            let dust = MessageInfo {
+                sender: deps.api.addr_make(&format!("dust{i}")),
+                funds: vec![],
+            };
+            let _res = execute(deps.as_mut(), env.clone(), dust, delegate_msg(None)).unwrap();
+        }
+        let late = MessageInfo {
+            sender: deps.api.addr_make("late"),
+            funds: vec![],
         };
+        let res =
+            execute(deps.as_mut(), env.clone(), late.clone(), delegate_msg(None)).unwrap_err();
+        assert_eq!(res, ContractError::TooManyDelegators { max: 50 });
 
-        let res = execute(deps.as_mut(), env.clone(), info.clone(), vote_msg).unwrap();
+        // delegating for a poll takes power on it
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            late.clone(),
+            delegate_msg(Some("poll2")),
+        )
+        .unwrap_err();
         assert_eq!(
-            res.attributes,
-            vec![
-                attr("action", "vote"),
-                attr("poll_id", poll_id),
-                attr("voter", sender),
-                attr("vote", "Option 1")
-            ]
+            res,
+            ContractError::NoVotingPower {
+                poll_id: "poll2".to_string()
+            }
         );
 
-        // Verify the vote was counted
-        let poll = POLLS.load(deps.as_ref().storage, poll_id).unwrap();
-        assert_eq!(poll.options[0].1, 1); // Option 1 should have 1 vote
-        assert_eq!(poll.options[1].1, 0); // Option 2 should have 0 votes
-        assert_eq!(poll.options[2].1, 0); // Option 3 should have 0 votes
+        // once the full delegate votes, global delegators without power on the poll lose
+        // their places
+        deps.querier
+            .bank
+            .update_balance(delegate.as_str(), vec![Coin::new(5u128, "ugov")]);
+        let dust0 = deps.api.addr_make("dust0");
+        deps.querier
+            .bank
+            .update_balance(dust0.as_str(), vec![Coin::new(3u128, "ugov")]);
+        let vote_poll2 = ExecuteMsg::Vote {
+            poll_id: "poll2".to_string(),
+            vote: Choice::Single("No".to_string()),
+        };
+        let res = execute(deps.as_mut(), env.clone(), delegate_info, vote_poll2).unwrap();
+        assert_eq!(res.attributes[4], attr("weight", "8"));
+
+        for (delegator, expected) in [
+            (dust0, Some(delegate.clone())),
+            (deps.api.addr_make("dust1"), None),
+        ] {
+            let query_msg = QueryMsg::GetDelegation {
+                delegator,
+                poll_id: None,
+            };
+            let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+            let delegation: GetDelegationResponse = from_json(&res).unwrap();
+            assert_eq!(delegation.delegate, expected);
+        }
+        let _res = execute(deps.as_mut(), env.clone(), late, delegate_msg(None)).unwrap();
     }
 
     #[test]
@@ -507,7 +5214,11 @@ mod tests {
         let all_polls: GetAllPollsResponse = from_json(&res).unwrap();
         assert_eq!(all_polls.polls.len(), 0);
 
-        let msg = InstantiateMsg { admin: None };
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
         let create_poll_msg = ExecuteMsg::CreatePoll {
@@ -518,6 +5229,7 @@ mod tests {
                 "Option 2".to_string(),
                 "Option 3".to_string(),
             ],
+            settings: PollSettings::default(),
         };
 
         let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
@@ -541,7 +5253,11 @@ mod tests {
             }],
         };
 
-        let msg = InstantiateMsg { admin: None };
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
         let create_poll_msg = ExecuteMsg::CreatePoll {
@@ -552,6 +5268,7 @@ mod tests {
                 "Option 2".to_string(),
                 "Option 3".to_string(),
             ],
+            settings: PollSettings::default(),
         };
 
         let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
@@ -584,7 +5301,11 @@ mod tests {
             }],
         };
 
-        let msg = InstantiateMsg { admin: None };
+        let msg = InstantiateMsg {
+            admin: None,
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
+        };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
         let create_poll_msg = ExecuteMsg::CreatePoll {
@@ -595,13 +5316,14 @@ mod tests {
                 "Option 2".to_string(),
                 "Option 3".to_string(),
             ],
+            settings: PollSettings::default(),
         };
 
         let _res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll_msg).unwrap();
 
         let vote_msg = ExecuteMsg::Vote {
             poll_id: "poll1".to_string(),
-            vote: "Option 1".to_string(),
+            vote: Choice::Single("Option 1".to_string()),
         };
 
         let _res = execute(deps.as_mut(), env.clone(), info.clone(), vote_msg).unwrap();
@@ -613,7 +5335,10 @@ mod tests {
 
         let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
         let user_vote: GetUserVoteResponse = from_json(&res).unwrap();
-        assert_eq!(user_vote.vote.unwrap().option, "Option 1");
+        assert_eq!(
+            user_vote.vote.unwrap().choice,
+            Choice::Single("Option 1".to_string())
+        );
 
         let query_msg2 = QueryMsg::GetUserVote {
             poll_id: "poll1".to_string(),
@@ -647,6 +5372,8 @@ mod tests {
 
         let msg = InstantiateMsg {
             admin: Some(admin.clone()),
+            fee: Coin::new(1000u128, "uatom"),
+            cw20_fee: None,
         };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -659,6 +5386,7 @@ mod tests {
                 "Option 2".to_string(),
                 "Option 3".to_string(),
             ],
+            settings: PollSettings::default(),
         };
         let res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll1_msg).unwrap();
         assert_eq!(
@@ -682,6 +5410,7 @@ mod tests {
                 "Option 2".to_string(),
                 "Option 3".to_string(),
             ],
+            settings: PollSettings::default(),
         };
 
         let res = execute(deps.as_mut(), env.clone(), info.clone(), create_poll2_msg).unwrap();
@@ -786,7 +5515,7 @@ mod tests {
 
         let vote_msg = ExecuteMsg::Vote {
             poll_id: "poll1".to_string(),
-            vote: "Option 1".to_string(),
+            vote: Choice::Single("Option 1".to_string()),
         };
 
         let res = execute(deps.as_mut(), env.clone(), info.clone(), vote_msg).unwrap_err();
@@ -0,0 +1,54 @@
+//! The parts of the cw20 token interface this contract speaks, mirroring the `cw20` package
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_json_binary, Addr, Binary, CosmosMsg, StdResult, Uint128, WasmMsg};
+
+/// Sent by a cw20 contract when tokens are transferred to us with `Send`
+#[cw_serde]
+pub struct Cw20ReceiveMsg {
+    pub sender: String,
+    pub amount: Uint128,
+    pub msg: Binary,
+}
+
+#[cw_serde]
+pub enum Cw20ExecuteMsg {
+    Transfer { recipient: String, amount: Uint128 },
+}
+
+#[cw_serde]
+pub enum Cw20QueryMsg {
+    Balance { address: String },
+}
+
+#[cw_serde]
+pub struct BalanceResponse {
+    pub balance: Uint128,
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct Cw20Coin {
+    pub address: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct Cw20CoinVerified {
+    pub address: Addr,
+    pub amount: Uint128,
+}
+
+impl Cw20CoinVerified {
+    pub fn transfer_msg(&self, recipient: &Addr) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: self.address.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: self.amount,
+            })?,
+            funds: vec![],
+        }
+        .into())
+    }
+}
@@ -0,0 +1,15 @@
+//! The parts of the cw4 group interface this contract speaks, mirroring the `cw4` package
+use cosmwasm_schema::cw_serde;
+
+#[cw_serde]
+pub enum Cw4QueryMsg {
+    Member {
+        addr: String,
+        at_height: Option<u64>,
+    },
+}
+
+#[cw_serde]
+pub struct MemberResponse {
+    pub weight: Option<u64>,
+}
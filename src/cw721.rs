@@ -0,0 +1,17 @@
+//! The parts of the cw721 NFT interface this contract speaks, mirroring the `cw721` package
+use cosmwasm_schema::cw_serde;
+
+#[cw_serde]
+pub enum Cw721QueryMsg {
+    /// Token ids owned by `owner`, paged by `start_after`
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct TokensResponse {
+    pub tokens: Vec<String>,
+}
@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal, StdError, Uint128};
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,22 +7,118 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
     #[error("Unauthorized")]
     Unauthorized {},
+
+    #[error("Contract is paused")]
+    Paused {},
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
     #[error("Too many options")]
     TooManyOptions {},
 
+    #[error("Too many questions")]
+    TooManyQuestions {},
+
     #[error("Poll not found: {poll_id}")]
     PollNotFound { poll_id: String },
 
+    #[error("Poll {poll_id} already exists")]
+    PollExists { poll_id: String },
+
+    #[error("Poll id {poll_id} is reserved for runoff polls")]
+    ReservedPollId { poll_id: String },
+
     #[error("Invalid vote option")]
     InvalidVote {},
 
+    #[error("Too many selections, at most {max} allowed")]
+    TooManySelections { max: u32 },
+
+    #[error("Ballot spends more than the {credits} credits available")]
+    OverBudget { credits: u64 },
+
+    #[error("Scores must be between {min} and {max}")]
+    ScoreOutOfRange { min: u64, max: u64 },
+
+    #[error("Estimate must be between {min} and {max}")]
+    EstimateOutOfRange { min: Decimal, max: Decimal },
+
+    #[error("Voting method settings do not fit the poll options")]
+    InvalidMethod {},
+
+    #[error("Only group members can vote in poll {poll_id}")]
+    NotMember { poll_id: String },
+
+    #[error("No voting power in poll {poll_id}")]
+    NoVotingPower { poll_id: String },
+
+    #[error("Token {address} is not accepted as a poll fee")]
+    UnsupportedToken { address: String },
+
     #[error("Insufficient funds")]
     InsufficientFunds {},
 
+    #[error("Paid {paid} but the fee is {fee}, pay the fee exactly")]
+    Overpaid { paid: Uint128, fee: Uint128 },
+
+    #[error("Fee denom can not be empty")]
+    InvalidFee {},
+
     #[error("Poll {poll_id} is closed, can not vote")]
     PollClosed { poll_id: String },
+
+    #[error("Poll {poll_id} is already closed")]
+    AlreadyClosed { poll_id: String },
+
+    #[error("Poll {poll_id} has not started yet, can not vote")]
+    PollNotStarted { poll_id: String },
+
+    #[error("Poll {poll_id} is still open, can not finalize or execute")]
+    PollNotEnded { poll_id: String },
+
+    #[error("Poll {poll_id} is already finalized")]
+    AlreadyFinalized { poll_id: String },
+
+    #[error("Poll {poll_id} has no messages to execute")]
+    NoActions { poll_id: String },
+
+    #[error("Poll {poll_id} did not pass with the option approving its messages")]
+    NotPassed { poll_id: String },
+
+    #[error("Messages of poll {poll_id} were already executed")]
+    AlreadyExecuted { poll_id: String },
+
+    #[error("Poll {poll_id} was cancelled")]
+    PollCancelled { poll_id: String },
+
+    #[error("Poll {poll_id} was vetoed")]
+    PollVetoed { poll_id: String },
+
+    #[error("Attached messages may only execute other contracts without funds")]
+    UnsupportedMessage {},
+
+    #[error("Quorum and threshold shares must be above zero and at most one")]
+    InvalidRules {},
+
+    #[error("Poll {poll_id} is not in its reveal phase")]
+    NotRevealPhase { poll_id: String },
+
+    #[error("No commitment to reveal in poll {poll_id}")]
+    NoCommitment { poll_id: String },
+
+    #[error("Revealed vote does not match the commitment")]
+    CommitmentMismatch {},
+
+    #[error("Can not delegate to yourself")]
+    SelfDelegation {},
+
+    #[error("Delegate already has {max} delegators")]
+    TooManyDelegators { max: u32 },
+
+    #[error("Poll must end after it starts and after the current block, and reveal after it ends")]
+    InvalidSchedule {},
 }
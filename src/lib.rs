@@ -1,8 +1,12 @@
 pub mod contract;
+pub mod cw20;
+pub mod cw4;
+pub mod cw721;
 mod error;
 pub mod helpers;
 pub mod msg;
 pub mod state;
+pub mod tally;
 
 #[cfg(test)]
 mod integration_test;
@@ -1,10 +1,18 @@
-use crate::state::{Ballot, Poll};
+use crate::cw20::{Cw20Coin, Cw20ReceiveMsg};
+use crate::state::{
+    Actions, Ballot, Choice, Poll, PollResult, Quorum, Runoff, Threshold, VotingMethod, VotingPower,
+};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Coin, Decimal256, Uint128};
+use cw_utils::{Expiration, Scheduled};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub admin: Option<String>,
+    /// Native fee for creating a poll, a zero amount makes polls free
+    pub fee: Coin,
+    /// Lets polls be paid for in this cw20 token as well as the native fee
+    pub cw20_fee: Option<Cw20Coin>,
 }
 
 #[cw_serde]
@@ -13,14 +21,117 @@ pub enum ExecuteMsg {
         poll_id: String,
         question: String,
         options: Vec<String>,
+        #[serde(default)]
+        settings: PollSettings,
+    },
+    /// Creates a survey, a poll whose ballots answer all of its questions at once
+    CreateSurvey {
+        poll_id: String,
+        title: String,
+        questions: Vec<Question>,
+        #[serde(default)]
+        settings: PollSettings,
     },
     Vote {
         poll_id: String,
-        vote: String,
+        vote: Choice,
+    },
+    /// Opens a commitment cast in a secret poll, counting the vote
+    Reveal {
+        poll_id: String,
+        vote: Choice,
+        salt: String,
     },
     ClosePoll {
         poll_id: String,
     },
+    /// Creates a poll paid for with the configured cw20 fee, `msg` is a `ReceiveMsg`
+    Receive(Cw20ReceiveMsg),
+    /// Records the final result of a poll that is closed or past its end, callable by anyone
+    FinalizePoll {
+        poll_id: String,
+    },
+    /// Hands the sender's voting power to `delegate` for one poll, or for every poll if
+    /// `poll_id` is unset. No `delegate` removes the delegation
+    Delegate {
+        delegate: Option<String>,
+        poll_id: Option<String>,
+    },
+    /// Dispatches the messages attached to a poll that ended with their approving option
+    /// passing, callable by anyone and only once
+    Execute {
+        poll_id: String,
+    },
+    /// Ends an open poll for good without counting it, admin only. The creation fee is
+    /// forfeited to the admin
+    Cancel {
+        poll_id: String,
+        reason: String,
+    },
+    /// Overrules a poll that ended and passed, admin only. Its result is recorded as vetoed
+    /// and its attached messages can no longer be executed
+    Veto {
+        poll_id: String,
+        reason: String,
+    },
+    /// Stops polls from being created, voted on, closed, finalized or executed until `Unpause`,
+    /// admin only
+    Pause {},
+    /// Lifts a `Pause`, admin only
+    Unpause {},
+}
+
+#[cw_serde]
+pub enum ReceiveMsg {
+    CreatePoll {
+        poll_id: String,
+        question: String,
+        options: Vec<String>,
+        #[serde(default)]
+        settings: PollSettings,
+    },
+    CreateSurvey {
+        poll_id: String,
+        title: String,
+        questions: Vec<Question>,
+        #[serde(default)]
+        settings: PollSettings,
+    },
+}
+
+/// One question of a new survey
+#[cw_serde]
+pub struct Question {
+    pub question: String,
+    pub options: Vec<String>,
+    /// How the question is answered and counted, single choice if unset
+    pub method: Option<VotingMethod>,
+}
+
+/// Optional rules for a new poll, everything defaults to the plain open-until-closed poll
+#[cw_serde]
+#[derive(Default)]
+pub struct PollSettings {
+    /// Block height or time at which voting opens
+    pub start: Option<Scheduled>,
+    /// Block height or time at which voting closes without a `ClosePoll`
+    pub end: Option<Expiration>,
+    /// Makes the poll secret, votes are commitments until `end` and revealed until `reveal_end`
+    pub reveal_end: Option<Expiration>,
+    /// How ballots are cast and counted, single choice if unset
+    pub method: Option<VotingMethod>,
+    /// Where ballot weights come from, one vote per voter if unset
+    pub power: Option<VotingPower>,
+    /// Participation required for the poll to pass
+    pub quorum: Option<Quorum>,
+    /// Support the leading option needs to pass, a single winner is enough if unset
+    pub threshold: Option<Threshold>,
+    /// Opens a second round between the two leading options of a single choice poll that
+    /// closes without a winner reaching the runoff share
+    pub runoff: Option<Runoff>,
+    /// Messages to dispatch if the poll passes with their approving option in the lead, only
+    /// for single choice and approval polls
+    pub actions: Option<Actions>,
 }
 
 #[cw_serde]
@@ -32,6 +143,22 @@ pub enum QueryMsg {
     GetPoll { poll_id: String },
     #[returns(GetUserVoteResponse)]
     GetUserVote { user: Addr, poll_id: String },
+    #[returns(GetTokenVoteResponse)]
+    GetTokenVote { poll_id: String, token_id: String },
+    #[returns(GetPollResultResponse)]
+    GetPollResult { poll_id: String },
+    /// Projects a finalized budgeting poll funded, `None` until then
+    #[returns(GetFundedProjectsResponse)]
+    GetFundedProjects { poll_id: String },
+    /// Options of a pairwise poll ranked by their current rating, `None` for other polls
+    #[returns(GetRankingResponse)]
+    GetRanking { poll_id: String },
+    /// Delegate in effect for a poll, or the global one if `poll_id` is unset
+    #[returns(GetDelegationResponse)]
+    GetDelegation {
+        delegator: Addr,
+        poll_id: Option<String>,
+    },
 }
 
 #[cw_serde]
@@ -48,3 +175,30 @@ pub struct GetPollResponse {
 pub struct GetUserVoteResponse {
     pub vote: Option<Ballot>,
 }
+
+#[cw_serde]
+pub struct GetTokenVoteResponse {
+    pub vote: Option<Ballot>,
+}
+
+#[cw_serde]
+pub struct GetPollResultResponse {
+    pub result: Option<PollResult>,
+}
+
+#[cw_serde]
+pub struct GetFundedProjectsResponse {
+    /// Funded projects with their cost, in order of support
+    pub funded: Option<Vec<(String, Uint128)>>,
+}
+
+#[cw_serde]
+pub struct GetRankingResponse {
+    /// Options with their Bradley-Terry strength, strongest first
+    pub ranking: Option<Vec<(String, Decimal256)>>,
+}
+
+#[cw_serde]
+pub struct GetDelegationResponse {
+    pub delegate: Option<Addr>,
+}
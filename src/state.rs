@@ -1,29 +1,560 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Coin};
+use cosmwasm_std::{
+    Addr, BlockInfo, Coin, CosmosMsg, Decimal, Decimal256, Empty, HexBinary, Uint128,
+};
 use cw_storage_plus::{Item, Map};
+use cw_utils::{Duration, Expiration, Scheduled};
+
+use crate::cw20::Cw20CoinVerified;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Config {
     pub admin: Addr,
     pub fee: Coin,
+    /// Alternative creation fee payable through the token's `Send` hook
+    pub cw20_fee: Option<Cw20CoinVerified>,
+    /// Set by the admin to stop polls from being created, voted on or closed
+    #[serde(default)]
+    pub paused: bool,
 }
 
+/// Creation fee held for a poll until it is closed
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Deposit {
+    Native(Coin),
+    Cw20(Cw20CoinVerified),
+}
+
+impl Deposit {
+    /// Amount paid, in whichever token it was paid with
+    pub fn amount(&self) -> Uint128 {
+        match self {
+            Deposit::Native(coin) => coin.amount,
+            Deposit::Cw20(coin) => coin.amount,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Poll {
     pub creator: Addr,
     pub question: String,
-    pub options: Vec<(String, u64)>,
+    pub options: Vec<(String, Uint128)>,
     pub is_active: bool,
+    /// Fee the creator paid, held in `ESCROWS` until it is refunded
+    pub deposit: Deposit,
+    /// Voting opens once this is reached, immediately if unset
+    pub start: Option<Scheduled>,
+    /// Voting closes once this is reached, only on `ClosePoll` if unset
+    pub end: Option<Expiration>,
+    /// Makes ballots secret: votes are commitments until `end` and get revealed until this
+    pub reveal_end: Option<Expiration>,
+    pub method: VotingMethod,
+    pub power: VotingPower,
+    pub quorum: Option<Quorum>,
+    pub threshold: Option<Threshold>,
+    /// Number of distinct voters, a changed vote is still one ballot
+    pub ballots: u64,
+    /// Summed weight of all ballots
+    pub total_weight: Uint128,
+    /// Weight each option was preferred with over each other option, as `(winner, loser,
+    /// weight)`, empty for other polls than pairwise ones
+    #[serde(default)]
+    pub comparisons: Vec<(String, String, Uint128)>,
+    /// Questions of a survey, answered together by every ballot, empty for other polls
+    #[serde(default)]
+    pub questions: Vec<SurveyQuestion>,
+    /// Second round to hold if no option reaches a majority by the time the poll closes
+    pub runoff: Option<Runoff>,
+    /// Id of the second round poll this poll led to
+    pub runoff_poll: Option<String>,
+    /// Id of the first round poll this poll is the runoff of
+    pub runoff_of: Option<String>,
+    /// Messages anyone can have the contract dispatch once the poll passes
+    pub actions: Option<Actions>,
+    /// Whether `actions` were dispatched already
+    #[serde(default)]
+    pub executed: bool,
+    /// Reason the admin gave for cancelling the poll, a cancelled poll is never counted and
+    /// its fee is not refunded
+    pub cancelled: Option<String>,
+    /// Reason the admin gave for vetoing the poll after it passed, its actions never run
+    pub vetoed: Option<String>,
+}
+
+/// Runoff rule of a poll, the second round is between the two leading options
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Runoff {
+    /// Share of the ballot weight the leading option needs to win outright
+    pub share: Decimal,
+    /// How long the second round stays open
+    pub duration: Duration,
+}
+
+/// Messages attached to a poll, dispatched by `Execute` once the poll is over and `option`
+/// leads the tallies and passes the poll's quorum and threshold
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Actions {
+    /// Option that approves the messages
+    pub option: String,
+    pub msgs: Vec<CosmosMsg>,
+}
+
+/// One question of a survey, with its own options and voting method
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SurveyQuestion {
+    pub question: String,
+    pub options: Vec<(String, Uint128)>,
+    pub method: VotingMethod,
+    /// Weight each option was preferred with over each other option on a pairwise question
+    #[serde(default)]
+    pub comparisons: Vec<(String, String, Uint128)>,
+}
+
+impl Poll {
+    pub fn has_started(&self, block: &BlockInfo) -> bool {
+        self.start.is_none_or(|start| start.is_triggered(block))
+    }
+
+    pub fn has_ended(&self, block: &BlockInfo) -> bool {
+        !self.is_active || self.end.is_some_and(|end| end.is_expired(block))
+    }
+
+    /// Past voting and, for secret polls, past the reveal phase too
+    pub fn can_finalize(&self, block: &BlockInfo) -> bool {
+        self.has_ended(block) && self.reveal_end.is_none_or(|end| end.is_expired(block))
+    }
+
+    /// Swaps a voter's previous ballot, if any, for a new one
+    pub fn replace_ballot(&mut self, old: Option<&Ballot>, new: &Ballot) {
+        match old {
+            Some(old) => self.remove_votes(old),
+            None => self.ballots += 1,
+        }
+        self.add_votes(new);
+    }
+
+    /// Adds the ballot's votes, times its weight, to every option the choice counts for
+    pub fn add_votes(&mut self, ballot: &Ballot) {
+        match &ballot.choice {
+            Choice::Survey(answers) => {
+                for (question, answer) in self.questions.iter_mut().zip(answers) {
+                    add_choice(
+                        &mut question.options,
+                        &mut question.comparisons,
+                        answer,
+                        ballot.weight,
+                    );
+                }
+            }
+            choice => add_choice(
+                &mut self.options,
+                &mut self.comparisons,
+                choice,
+                ballot.weight,
+            ),
+        }
+        self.total_weight += ballot.weight;
+    }
+
+    /// Takes back the votes of a ballot that is being replaced, with the weight it was cast with
+    pub fn remove_votes(&mut self, ballot: &Ballot) {
+        match &ballot.choice {
+            Choice::Survey(answers) => {
+                for (question, answer) in self.questions.iter_mut().zip(answers) {
+                    remove_choice(
+                        &mut question.options,
+                        &mut question.comparisons,
+                        answer,
+                        ballot.weight,
+                    );
+                }
+            }
+            choice => remove_choice(
+                &mut self.options,
+                &mut self.comparisons,
+                choice,
+                ballot.weight,
+            ),
+        }
+        self.total_weight -= ballot.weight;
+    }
+}
+
+fn add_choice(
+    tallies: &mut [(String, Uint128)],
+    comparisons: &mut Vec<(String, String, Uint128)>,
+    choice: &Choice,
+    weight: Uint128,
+) {
+    for (option, votes) in choice.counted() {
+        if let Some((_, count)) = tallies.iter_mut().find(|(name, _)| name == option) {
+            *count += weight * Uint128::from(votes);
+        }
+    }
+    if let Choice::Pairwise(pairs) = choice {
+        for (winner, loser) in pairs {
+            let pair = comparisons
+                .iter_mut()
+                .find(|(won, lost, _)| won == winner && lost == loser);
+            match pair {
+                Some((_, _, count)) => *count += weight,
+                None => comparisons.push((winner.clone(), loser.clone(), weight)),
+            }
+        }
+    }
+}
+
+fn remove_choice(
+    tallies: &mut [(String, Uint128)],
+    comparisons: &mut Vec<(String, String, Uint128)>,
+    choice: &Choice,
+    weight: Uint128,
+) {
+    for (option, votes) in choice.counted() {
+        if let Some((_, count)) = tallies.iter_mut().find(|(name, _)| name == option) {
+            *count -= weight * Uint128::from(votes);
+        }
+    }
+    if let Choice::Pairwise(pairs) = choice {
+        for (winner, loser) in pairs {
+            let pair = comparisons
+                .iter_mut()
+                .find(|(won, lost, _)| won == winner && lost == loser);
+            if let Some((_, _, count)) = pair {
+                *count -= weight;
+            }
+        }
+        // pairs nobody prefers anymore take no room in the poll
+        comparisons.retain(|(_, _, count)| !count.is_zero());
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VotingMethod {
+    /// One option per voter
+    SingleChoice {},
+    /// Any subset of the options, at most `max_selections` of them if set
+    Approval { max_selections: Option<u32> },
+    /// Voters rank options, counted by instant-runoff at finalization
+    RankedChoice {},
+    /// Voters rank options, the Condorcet-consistent Schulze method picks the winner at finalization
+    Schulze {},
+    /// Voters rank options, single transferable vote fills `seats` winners at finalization
+    SingleTransferable { seats: u32 },
+    /// Voters spread votes over the options, n votes on one option cost n² of `credits`
+    Quadratic { credits: u64 },
+    /// Voters score every option from `min` to `max`, such as 0 to 5 stars
+    Score { min: u64, max: u64 },
+    /// Voters approve projects costing `costs`, in the order of the options, and finalization
+    /// funds the best supported ones that fit in `budget`
+    Budgeting {
+        budget: Uint128,
+        costs: Vec<Uint128>,
+    },
+    /// Voters answer with a number from `min` to `max` instead of picking options, finalization
+    /// reports the mean, the median and the `quantiles`, each between zero and one
+    Estimate {
+        min: Decimal,
+        max: Decimal,
+        quantiles: Vec<Decimal>,
+    },
+    /// Several questions, each with its own method, see `Poll.questions`
+    Survey {},
+    /// Voters compare pairs of options, a Bradley-Terry fit of every comparison rates the options
+    Pairwise {},
+}
+
+/// Where a voter's ballot weight comes from, read when the ballot is cast
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VotingPower {
+    /// Every ballot counts once
+    Equal {},
+    /// Ballots weigh the voter's bank balance of `denom`
+    NativeBalance { denom: String },
+    /// Ballots weigh the voter's balance in the cw20 token at `contract`
+    Cw20Balance { contract: Addr },
+    /// Only members of the cw4 group at `contract` vote, with their member weight
+    Cw4Group { contract: Addr },
+    /// Every token of the cw721 collection at `contract` casts its own ballot, whoever holds it
+    Cw721Tokens { contract: Addr },
+}
+
+/// What a voter picked, its shape has to match the poll's `VotingMethod`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Choice {
+    Single(String),
+    Approval(Vec<String>),
+    /// Options from most to least preferred, unranked options come last
+    Ranked(Vec<String>),
+    /// Votes put on each option of a quadratic poll
+    Quadratic(Vec<(String, u64)>),
+    /// Score given to each option of a score poll
+    Score(Vec<(String, u64)>),
+    /// Answer to an estimation poll
+    Estimate(Decimal),
+    /// Answers to every question of a survey, in order
+    Survey(Vec<Choice>),
+    /// Pairs of options compared on a pairwise poll, the preferred option of each pair first
+    Pairwise(Vec<(String, String)>),
+    /// sha256 of the JSON encoded choice followed by a salt, cast while a secret poll is open
+    Commitment(HexBinary),
+}
+
+impl Choice {
+    /// Every option the choice names
+    pub fn selections(&self) -> Vec<&str> {
+        match self {
+            Choice::Single(option) => vec![option],
+            Choice::Approval(options) | Choice::Ranked(options) => {
+                options.iter().map(String::as_str).collect()
+            }
+            Choice::Quadratic(votes) | Choice::Score(votes) => {
+                votes.iter().map(|(option, _)| option.as_str()).collect()
+            }
+            Choice::Pairwise(pairs) => pairs
+                .iter()
+                .flat_map(|(winner, loser)| [winner.as_str(), loser.as_str()])
+                .collect(),
+            Choice::Estimate(_) | Choice::Survey(_) | Choice::Commitment(_) => vec![],
+        }
+    }
+
+    /// Votes the choice adds to options in `Poll.options`, a ranking only counts its first preference
+    /// and a comparison counts for the preferred option
+    pub fn counted(&self) -> Vec<(&str, u64)> {
+        match self {
+            Choice::Pairwise(pairs) => pairs
+                .iter()
+                .map(|(winner, _)| (winner.as_str(), 1))
+                .collect(),
+            Choice::Ranked(options) => options
+                .iter()
+                .take(1)
+                .map(|option| (option.as_str(), 1))
+                .collect(),
+            Choice::Quadratic(votes) | Choice::Score(votes) => votes
+                .iter()
+                .map(|(option, votes)| (option.as_str(), *votes))
+                .collect(),
+            _ => self
+                .selections()
+                .into_iter()
+                .map(|option| (option, 1))
+                .collect(),
+        }
+    }
+
+    /// Voice credits a quadratic allocation spends, `None` if it does not fit in a u64
+    pub fn credits(&self) -> Option<u64> {
+        match self {
+            Choice::Quadratic(votes) => votes.iter().try_fold(0u64, |total, (_, votes)| {
+                total.checked_add(votes.checked_mul(*votes)?)
+            }),
+            _ => Some(0),
+        }
+    }
 }
 
 // Derive JSON serialization
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Ballot {
+    pub choice: Choice,
+    /// Voting power the ballot was cast with, including any delegated to the voter
+    pub weight: Uint128,
+    /// Delegators whose power is part of `weight`, with how much each brought
+    #[serde(default)]
+    pub delegators: Vec<(Addr, Uint128)>,
+}
+
+/// Minimum participation for a poll result to count
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Quorum {
+    /// At least `count` ballots
+    Ballots { count: u64 },
+    /// Ballots from at least `share` of an electorate of `electorate` voters
+    Share { share: Decimal, electorate: u64 },
+}
+
+/// Support the leading option needs, measured against the votes cast
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Threshold {
+    /// Strictly more than half of the votes
+    Majority {},
+    /// At least `share` of the votes
+    Supermajority { share: Decimal },
+    /// At least `count` votes, in ballot weight
+    AbsoluteCount { count: Uint128 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Passed,
+    Rejected,
+    QuorumNotMet,
+    /// Passed, but overruled by the admin
+    Vetoed,
+}
+
+/// How a finalized poll came out on its raw tallies
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Winner {
+        option: String,
+    },
+    Tie {
+        options: Vec<String>,
+    },
+    /// Winners of a multi-seat poll, in the order they were elected
+    Elected {
+        options: Vec<String>,
+    },
+    /// Median answer of an estimation poll
+    Estimate {
+        value: Decimal,
+    },
+    /// A survey got answers, its questions each have their own outcome
+    Surveyed {},
+    NoVotes {},
+}
+
+/// One round of an instant-runoff count
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RunoffRound {
+    /// Votes of each option still in the race
+    pub tallies: Vec<(String, Uint128)>,
+    /// Options knocked out at the end of the round
+    pub eliminated: Vec<String>,
+    /// Weight of ballots with no continuing option left
+    pub exhausted: Uint128,
+}
+
+/// One round of a single transferable vote count, votes are fractional once surpluses move
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StvRound {
+    /// Votes of each hopeful option at the start of the round
+    pub tallies: Vec<(String, Decimal256)>,
+    /// Options that reached the quota, or filled the last seats, this round
+    pub elected: Vec<String>,
+    /// Option knocked out this round
+    pub eliminated: Vec<String>,
+    /// Votes each option still in the race gained from this round's surpluses and eliminations
+    pub transfers: Vec<(String, Decimal256)>,
+    /// Votes of ballots with no hopeful option left after this round's transfers
+    pub exhausted: Decimal256,
+}
+
+/// How one question of a survey came out
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct QuestionResult {
+    pub question: String,
+    pub tallies: Vec<(String, Uint128)>,
+    pub outcome: Outcome,
+    pub detail: Option<ResultDetail>,
+}
+
+/// Answers of an estimation poll, each weighed by its ballot's weight
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EstimateSummary {
+    pub mean: Decimal,
+    pub median: Decimal,
+    /// Each requested quantile with the smallest answer that reaches it
+    pub quantiles: Vec<(Decimal, Decimal)>,
+}
+
+/// How an option of a score poll was rated
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OptionScore {
     pub option: String,
+    /// Scores summed, each weighed by its ballot's weight
+    pub sum: Uint128,
+    /// `sum` over the total ballot weight
+    pub mean: Decimal,
+    /// Number of ballots that scored the option
+    pub votes: u64,
+}
+
+/// Method specific working behind a result
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultDetail {
+    InstantRunoff {
+        rounds: Vec<RunoffRound>,
+    },
+    /// Matrices are indexed in the order of the poll's options, `pairwise[i][j]` is the
+    /// weight preferring option i over option j
+    Schulze {
+        pairwise: Vec<Vec<Uint128>>,
+        strongest_paths: Vec<Vec<Uint128>>,
+    },
+    Score {
+        scores: Vec<OptionScore>,
+    },
+    Estimate {
+        summary: EstimateSummary,
+    },
+    /// Result of every question of a survey, in order
+    Survey {
+        questions: Vec<QuestionResult>,
+    },
+    /// Projects funded in order of support, with their cost
+    Budgeting {
+        funded: Vec<(String, Uint128)>,
+        spent: Uint128,
+    },
+    /// Droop quota and the rounds of a single transferable vote count
+    SingleTransferable {
+        quota: Uint128,
+        rounds: Vec<StvRound>,
+    },
+    /// Bradley-Terry strength of every option, strongest first
+    Pairwise {
+        ratings: Vec<(String, Decimal256)>,
+    },
+}
+
+/// Canonical outcome of a poll, written once by `FinalizePoll` and never changed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PollResult {
+    pub tallies: Vec<(String, Uint128)>,
+    pub outcome: Outcome,
+    pub decision: Decision,
+    pub ballots: u64,
+    pub total_weight: Uint128,
+    /// Commitments of secret polls that were never revealed and so not counted
+    pub unrevealed: u64,
+    pub detail: Option<ResultDetail>,
+    pub finalized_height: u64,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const POLLS: Map<&str, Poll> = Map::new("polls");
-pub const BALLOTS: Map<(Addr, &str), Ballot> = Map::new("ballots");
+/// Ballots keyed by (poll_id, voter) so a poll's ballots can be ranged over
+pub const BALLOTS: Map<(&str, &Addr), Ballot> = Map::new("ballots");
+/// Ballots of `Cw721Tokens` polls keyed by (poll_id, token_id), a token keeps its one
+/// ballot when it changes hands
+pub const TOKEN_BALLOTS: Map<(&str, &str), Ballot> = Map::new("token_ballots");
+pub const RESULTS: Map<&str, PollResult> = Map::new("results");
+/// Fee paid for each poll that the contract still holds, removed once it is refunded or
+/// forfeited so it can only leave the contract once
+pub const ESCROWS: Map<&str, Deposit> = Map::new("escrows");
+
+/// Scope of a delegation that applies to every poll without a poll specific one
+pub const GLOBAL_SCOPE: &str = "";
+/// (scope, delegator) -> delegate, the scope is a poll_id or `GLOBAL_SCOPE`
+pub const DELEGATIONS: Map<(&str, &Addr), Addr> = Map::new("delegations");
+/// Reverse index of `DELEGATIONS`, (scope, delegate, delegator)
+pub const DELEGATORS: Map<(&str, &Addr, &Addr), Empty> = Map::new("delegators");
+/// (delegator, poll_id) -> delegate whose ballot currently carries the delegator's power, keyed
+/// by delegator first so a changed delegation finds every poll it reaches
+pub const PROXIES: Map<(&Addr, &str), Addr> = Map::new("delegator_proxies");
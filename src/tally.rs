@@ -0,0 +1,808 @@
+use cosmwasm_std::{Decimal, Decimal256, Uint128};
+
+use crate::state::{
+    Decision, EstimateSummary, OptionScore, Outcome, Quorum, RunoffRound, StvRound, Threshold,
+};
+
+/// Minorization-maximization rounds a Bradley-Terry fit runs
+const BRADLEY_TERRY_ROUNDS: usize = 50;
+
+/// Picks the option with the most votes, reporting every leader when they are level
+pub fn plurality(tallies: &[(String, Uint128)]) -> Outcome {
+    let top = tallies
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or_default();
+    if top.is_zero() {
+        return Outcome::NoVotes {};
+    }
+
+    let mut leaders: Vec<String> = tallies
+        .iter()
+        .filter(|(_, count)| *count == top)
+        .map(|(option, _)| option.clone())
+        .collect();
+
+    if leaders.len() == 1 {
+        Outcome::Winner {
+            option: leaders.remove(0),
+        }
+    } else {
+        Outcome::Tie { options: leaders }
+    }
+}
+
+/// Applies a poll's quorum and pass threshold to its plurality outcome. Quorum counts
+/// ballots, threshold shares are taken of the total ballot weight so approval polls
+/// measure approval rates
+pub fn decide(
+    quorum: Option<&Quorum>,
+    threshold: Option<&Threshold>,
+    ballots: u64,
+    total_weight: Uint128,
+    tallies: &[(String, Uint128)],
+    outcome: &Outcome,
+) -> Decision {
+    let quorum_met = match quorum {
+        None => true,
+        Some(Quorum::Ballots { count }) => ballots >= *count,
+        Some(Quorum::Share { share, electorate }) => {
+            Decimal::from_ratio(ballots, *electorate) >= *share
+        }
+    };
+    if !quorum_met {
+        return Decision::QuorumNotMet;
+    }
+
+    let leader = match outcome {
+        Outcome::Winner { option } => option,
+        // thresholds measure a single winner, filling every seat or answering is enough
+        Outcome::Elected { .. } | Outcome::Estimate { .. } | Outcome::Surveyed {} => {
+            return Decision::Passed
+        }
+        Outcome::Tie { .. } | Outcome::NoVotes {} => return Decision::Rejected,
+    };
+    let support = tallies
+        .iter()
+        .find(|(option, _)| option == leader)
+        .map_or(Uint128::zero(), |(_, count)| *count);
+    let passed = match threshold {
+        None => true,
+        Some(Threshold::Majority {}) => support > total_weight - support,
+        Some(Threshold::Supermajority { share }) => {
+            Decimal::from_ratio(support, total_weight) >= *share
+        }
+        Some(Threshold::AbsoluteCount { count }) => support >= *count,
+    };
+    if passed {
+        Decision::Passed
+    } else {
+        Decision::Rejected
+    }
+}
+
+/// Funds projects from the most supported down, skipping any that no longer fit the budget.
+/// Projects nobody approved are never funded, level support keeps the order of `tallies`.
+/// Returns the funded projects with their cost and the amount spent
+pub fn fund_projects(
+    tallies: &[(String, Uint128)],
+    costs: &[Uint128],
+    budget: Uint128,
+) -> (Vec<(String, Uint128)>, Uint128) {
+    let mut projects: Vec<(&String, Uint128, Uint128)> = tallies
+        .iter()
+        .zip(costs)
+        .map(|((option, support), cost)| (option, *support, *cost))
+        .filter(|(_, support, _)| !support.is_zero())
+        .collect();
+    projects.sort_by(|(_, a, _), (_, b, _)| b.cmp(a));
+
+    let mut funded = vec![];
+    let mut spent = Uint128::zero();
+    for (option, _, cost) in projects {
+        if spent + cost <= budget {
+            spent += cost;
+            funded.push((option.clone(), cost));
+        }
+    }
+    (funded, spent)
+}
+
+/// Weighted mean, median and quantiles of estimates given as `(answer, weight)`, `None`
+/// without any weight. A quantile q is the smallest answer at which the answers so far
+/// carry at least q of the total weight
+pub fn estimate(
+    estimates: &[(Decimal, Uint128)],
+    quantiles: &[Decimal],
+) -> Option<EstimateSummary> {
+    let total: Uint128 = estimates.iter().map(|(_, weight)| weight).sum();
+    if total.is_zero() {
+        return None;
+    }
+
+    let mut sorted = estimates.to_vec();
+    sorted.sort_by_key(|(answer, _)| *answer);
+    let quantile = |q: Decimal| -> Decimal {
+        let target = Decimal256::from_ratio(total, 1u128) * Decimal256::from(q);
+        let mut reached = Uint128::zero();
+        for (answer, weight) in &sorted {
+            reached += weight;
+            if !weight.is_zero() && Decimal256::from_ratio(reached, 1u128) >= target {
+                return *answer;
+            }
+        }
+        sorted[sorted.len() - 1].0
+    };
+
+    // the mean lies between the smallest and largest answer, so it fits back in a Decimal
+    let weighted_sum: Decimal256 = sorted
+        .iter()
+        .map(|(answer, weight)| Decimal256::from(*answer) * Decimal256::from_ratio(*weight, 1u128))
+        .sum();
+    let mean = weighted_sum / Decimal256::from_ratio(total, 1u128);
+
+    Some(EstimateSummary {
+        mean: Decimal::try_from(mean).expect("mean is within the answers"),
+        median: quantile(Decimal::percent(50)),
+        quantiles: quantiles.iter().map(|q| (*q, quantile(*q))).collect(),
+    })
+}
+
+/// Per option summary of a score poll, whose ballots score every option so each option
+/// was scored by every ballot
+pub fn scores(
+    tallies: &[(String, Uint128)],
+    ballots: u64,
+    total_weight: Uint128,
+) -> Vec<OptionScore> {
+    tallies
+        .iter()
+        .map(|(option, sum)| OptionScore {
+            option: option.clone(),
+            sum: *sum,
+            mean: Decimal::checked_from_ratio(*sum, total_weight).unwrap_or_default(),
+            votes: ballots,
+        })
+        .collect()
+}
+
+/// Instant-runoff over ranked ballots given as `(ranking, weight)`.
+///
+/// Each round counts every ballot for its highest ranked option still in the race. An option
+/// with a majority of the counted votes wins, otherwise all options sharing the lowest count
+/// are eliminated together. If every remaining option is level the poll ends in a tie.
+pub fn instant_runoff(
+    options: &[String],
+    ballots: &[(Vec<String>, Uint128)],
+) -> (Outcome, Vec<RunoffRound>) {
+    let mut continuing: Vec<String> = options.to_vec();
+    let mut rounds = vec![];
+
+    loop {
+        let mut tallies: Vec<(String, Uint128)> = continuing
+            .iter()
+            .map(|option| (option.clone(), Uint128::zero()))
+            .collect();
+        let mut exhausted = Uint128::zero();
+        for (ranking, weight) in ballots {
+            let preference = ranking.iter().find(|option| continuing.contains(option));
+            match preference {
+                Some(preference) => {
+                    let (_, count) = tallies
+                        .iter_mut()
+                        .find(|(option, _)| option == preference)
+                        .expect("preference is a continuing option");
+                    *count += weight;
+                }
+                None => exhausted += weight,
+            }
+        }
+
+        let active: Uint128 = tallies.iter().map(|(_, count)| count).sum();
+        let top = tallies
+            .iter()
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or_default();
+        let bottom = tallies
+            .iter()
+            .map(|(_, count)| *count)
+            .min()
+            .unwrap_or_default();
+
+        let outcome = if active.is_zero() {
+            Some(Outcome::NoVotes {})
+        } else if top > active - top {
+            let (winner, _) = tallies.iter().find(|(_, count)| *count == top).unwrap();
+            Some(Outcome::Winner {
+                option: winner.clone(),
+            })
+        } else if top == bottom {
+            Some(Outcome::Tie {
+                options: continuing.clone(),
+            })
+        } else {
+            None
+        };
+
+        let eliminated: Vec<String> = match outcome {
+            Some(_) => vec![],
+            None => tallies
+                .iter()
+                .filter(|(_, count)| *count == bottom)
+                .map(|(option, _)| option.clone())
+                .collect(),
+        };
+        continuing.retain(|option| !eliminated.contains(option));
+        rounds.push(RunoffRound {
+            tallies,
+            eliminated,
+            exhausted,
+        });
+
+        if let Some(outcome) = outcome {
+            return (outcome, rounds);
+        }
+    }
+}
+
+/// Schulze method over ranked ballots given as `(ranking, weight)`, unranked options share
+/// the last place. Returns the outcome with the pairwise preference matrix and the strongest
+/// path matrix, both indexed in the order of `options`.
+///
+/// `pairwise[i][j]` is the weight of ballots preferring option i over option j. A path is as
+/// strong as its weakest link, and only links with a pairwise win count. Every option whose
+/// strongest paths beat or equal those of all rivals wins, more than one such option is a tie.
+pub fn schulze(
+    options: &[String],
+    ballots: &[(Vec<String>, Uint128)],
+) -> (Outcome, Vec<Vec<Uint128>>, Vec<Vec<Uint128>>) {
+    let n = options.len();
+    let mut pairwise = vec![vec![Uint128::zero(); n]; n];
+    for (ranking, weight) in ballots {
+        let place: Vec<usize> = options
+            .iter()
+            .map(|option| {
+                ranking
+                    .iter()
+                    .position(|ranked| ranked == option)
+                    .unwrap_or(ranking.len())
+            })
+            .collect();
+        for i in 0..n {
+            for j in 0..n {
+                if place[i] < place[j] {
+                    pairwise[i][j] += weight;
+                }
+            }
+        }
+    }
+
+    let mut paths = vec![vec![Uint128::zero(); n]; n];
+    for i in 0..n {
+        for j in 0..n {
+            if i != j && pairwise[i][j] > pairwise[j][i] {
+                paths[i][j] = pairwise[i][j];
+            }
+        }
+    }
+    for k in 0..n {
+        for i in 0..n {
+            for j in 0..n {
+                if i != k && j != k && i != j {
+                    let through_k = paths[i][k].min(paths[k][j]);
+                    if through_k > paths[i][j] {
+                        paths[i][j] = through_k;
+                    }
+                }
+            }
+        }
+    }
+
+    let outcome = if ballots.iter().all(|(_, weight)| weight.is_zero()) {
+        Outcome::NoVotes {}
+    } else {
+        let mut winners: Vec<String> = (0..n)
+            .filter(|&i| (0..n).all(|j| paths[i][j] >= paths[j][i]))
+            .map(|i| options[i].clone())
+            .collect();
+        if winners.len() == 1 {
+            Outcome::Winner {
+                option: winners.remove(0),
+            }
+        } else {
+            Outcome::Tie { options: winners }
+        }
+    };
+
+    (outcome, pairwise, paths)
+}
+
+/// Single transferable vote over ranked ballots given as `(ranking, weight)`, electing `seats`
+/// options. Returns the outcome with the Droop quota and the rounds of the count.
+///
+/// Each round counts every ballot, at its current value, for its highest ranked hopeful option.
+/// Options reaching the quota are elected and the surplus above the quota moves on with their
+/// ballots, whose values shrink by the surplus share. If nobody reaches the quota the lowest
+/// option is eliminated and its ballots move on whole, a tie knocks out the option listed last.
+/// Once the hopefuls left fit the open seats they are all elected.
+pub fn single_transferable_vote(
+    options: &[String],
+    ballots: &[(Vec<String>, Uint128)],
+    seats: u32,
+) -> (Outcome, Uint128, Vec<StvRound>) {
+    let total: Uint128 = ballots.iter().map(|(_, weight)| weight).sum();
+    let quota = total / Uint128::from(seats + 1) + Uint128::one();
+    if total.is_zero() {
+        return (Outcome::NoVotes {}, quota, vec![]);
+    }
+    let quota_votes = Decimal256::from_ratio(quota, 1u128);
+
+    let mut values: Vec<Decimal256> = ballots
+        .iter()
+        .map(|(_, weight)| Decimal256::from_ratio(*weight, 1u128))
+        .collect();
+    let mut hopeful: Vec<String> = options.to_vec();
+    let mut elected: Vec<String> = vec![];
+    let mut rounds: Vec<StvRound> = vec![];
+
+    loop {
+        let holders: Vec<Option<&String>> = ballots
+            .iter()
+            .map(|(ranking, _)| ranking.iter().find(|option| hopeful.contains(option)))
+            .collect();
+        let mut tallies: Vec<(String, Decimal256)> = hopeful
+            .iter()
+            .map(|option| (option.clone(), Decimal256::zero()))
+            .collect();
+        let mut exhausted = Decimal256::zero();
+        for (holder, value) in holders.iter().zip(&values) {
+            match holder {
+                Some(holder) => {
+                    let (_, count) = tallies
+                        .iter_mut()
+                        .find(|(option, _)| option == *holder)
+                        .expect("holder is a hopeful option");
+                    *count += value;
+                }
+                None => exhausted += value,
+            }
+        }
+
+        // what the previous round moved shows in how the remaining hopefuls grew
+        if let Some(last) = rounds.last_mut() {
+            last.transfers = tallies
+                .iter()
+                .map(|(option, count)| {
+                    let (_, before) = last
+                        .tallies
+                        .iter()
+                        .find(|(previous, _)| previous == option)
+                        .expect("hopefuls were hopeful the round before");
+                    (option.clone(), *count - *before)
+                })
+                .collect();
+            last.exhausted = exhausted;
+        }
+
+        let open_seats = seats as usize - elected.len();
+        let round_elected: Vec<String> = if hopeful.len() <= open_seats {
+            hopeful.clone()
+        } else {
+            let mut reached: Vec<&(String, Decimal256)> = tallies
+                .iter()
+                .filter(|(_, count)| *count >= quota_votes)
+                .collect();
+            reached.sort_by(|(_, a), (_, b)| b.cmp(a));
+            reached
+                .into_iter()
+                .take(open_seats)
+                .map(|(option, _)| option.clone())
+                .collect()
+        };
+
+        let mut eliminated = vec![];
+        if round_elected.is_empty() {
+            let (lowest, _) = tallies
+                .iter()
+                .rev()
+                .min_by_key(|(_, count)| *count)
+                .expect("more hopefuls than open seats");
+            eliminated.push(lowest.clone());
+        } else {
+            for (option, count) in tallies
+                .iter()
+                .filter(|(option, _)| round_elected.contains(option))
+            {
+                // options filling the last seats may hold no votes, and so nothing to pass on
+                if count.is_zero() {
+                    continue;
+                }
+                let surplus = count.saturating_sub(quota_votes);
+                for (holder, value) in holders.iter().zip(values.iter_mut()) {
+                    if *holder == Some(option) {
+                        *value = *value * surplus / *count;
+                    }
+                }
+            }
+        }
+
+        hopeful.retain(|option| !round_elected.contains(option) && !eliminated.contains(option));
+        elected.extend(round_elected.iter().cloned());
+        rounds.push(StvRound {
+            tallies,
+            elected: round_elected,
+            eliminated,
+            transfers: vec![],
+            exhausted,
+        });
+
+        if elected.len() == seats as usize {
+            return (Outcome::Elected { options: elected }, quota, rounds);
+        }
+    }
+}
+
+/// Bradley-Terry strengths of the options fitted to comparisons given as
+/// `(winner, loser, weight)`. Returns the outcome with every option's strength, strongest first
+/// and level options in the order they were listed.
+///
+/// The fit runs a fixed number of minorization-maximization rounds. Weights are taken relative
+/// to the heaviest comparison, and every option also wins and loses one such comparison against
+/// a virtual opponent of strength one, which keeps options that never won or never lost at a
+/// finite strength and leaves options nobody compared at one.
+pub fn bradley_terry(
+    options: &[String],
+    comparisons: &[(&str, &str, Uint128)],
+) -> (Outcome, Vec<(String, Decimal256)>) {
+    let index = |name: &str| options.iter().position(|option| option == name);
+    let heaviest = comparisons
+        .iter()
+        .map(|(_, _, weight)| *weight)
+        .max()
+        .unwrap_or_default();
+
+    // weight every option won with and the weight of the comparisons between each pair
+    let mut wins = vec![Decimal256::one(); options.len()];
+    let mut games: Vec<(usize, usize, Decimal256)> = vec![];
+    for (winner, loser, weight) in comparisons {
+        let (Some(w), Some(l)) = (index(winner), index(loser)) else {
+            continue;
+        };
+        if weight.is_zero() {
+            continue;
+        }
+        let weight = Decimal256::from_ratio(*weight, heaviest);
+        wins[w] += weight;
+        let pair = (w.min(l), w.max(l));
+        match games.iter_mut().find(|(i, j, _)| (*i, *j) == pair) {
+            Some((_, _, count)) => *count += weight,
+            None => games.push((pair.0, pair.1, weight)),
+        }
+    }
+
+    let two = Decimal256::percent(200);
+    let mut strengths = vec![Decimal256::one(); options.len()];
+    for _ in 0..BRADLEY_TERRY_ROUNDS {
+        let mut met: Vec<Decimal256> = strengths
+            .iter()
+            .map(|strength| two / (*strength + Decimal256::one()))
+            .collect();
+        for (i, j, count) in &games {
+            // two options rated down to nothing tell each other nothing more
+            if let Ok(share) = count.checked_div(strengths[*i] + strengths[*j]) {
+                met[*i] += share;
+                met[*j] += share;
+            }
+        }
+        strengths = wins.iter().zip(met).map(|(won, met)| *won / met).collect();
+    }
+
+    let mut ratings: Vec<(String, Decimal256)> = options.iter().cloned().zip(strengths).collect();
+    ratings.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    let outcome = if games.is_empty() {
+        Outcome::NoVotes {}
+    } else {
+        let mut leaders: Vec<String> = ratings
+            .iter()
+            .take_while(|(_, strength)| *strength == ratings[0].1)
+            .map(|(option, _)| option.clone())
+            .collect();
+        if leaders.len() == 1 {
+            Outcome::Winner {
+                option: leaders.remove(0),
+            }
+        } else {
+            Outcome::Tie { options: leaders }
+        }
+    };
+
+    (outcome, ratings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weighted(ranking_options: &[&str], weight: u128) -> (Vec<String>, Uint128) {
+        (ranking(ranking_options), Uint128::new(weight))
+    }
+
+    fn ranking(options: &[&str]) -> Vec<String> {
+        options.iter().map(|option| option.to_string()).collect()
+    }
+
+    #[test]
+    fn test_instant_runoff_transfers_eliminated_votes() {
+        let options = ranking(&["A", "B", "C"]);
+        let ballots = vec![
+            weighted(&["A", "B"], 5),
+            weighted(&["B", "A"], 4),
+            weighted(&["C", "B"], 2),
+            weighted(&["C"], 1),
+        ];
+
+        let (outcome, rounds) = instant_runoff(&options, &ballots);
+
+        // C is eliminated first, one C ballot moves to B and the other is exhausted
+        assert_eq!(
+            outcome,
+            Outcome::Winner {
+                option: "B".to_string()
+            }
+        );
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].eliminated, ranking(&["C"]));
+        assert_eq!(
+            rounds[1].tallies,
+            vec![
+                ("A".to_string(), Uint128::new(5)),
+                ("B".to_string(), Uint128::new(6))
+            ]
+        );
+        assert_eq!(rounds[1].exhausted, Uint128::one());
+    }
+
+    #[test]
+    fn test_instant_runoff_tie() {
+        let options = ranking(&["A", "B"]);
+        let ballots = vec![weighted(&["A"], 1), weighted(&["B"], 1)];
+
+        let (outcome, rounds) = instant_runoff(&options, &ballots);
+        assert_eq!(
+            outcome,
+            Outcome::Tie {
+                options: ranking(&["A", "B"])
+            }
+        );
+        assert_eq!(rounds.len(), 1);
+    }
+
+    #[test]
+    fn test_schulze_strongest_path_winner() {
+        let options = ranking(&["A", "B", "C", "D", "E"]);
+        let ballots = vec![
+            weighted(&["A", "C", "B", "E", "D"], 5),
+            weighted(&["A", "D", "E", "C", "B"], 5),
+            weighted(&["B", "E", "D", "A", "C"], 8),
+            weighted(&["C", "A", "B", "E", "D"], 3),
+            weighted(&["C", "A", "E", "B", "D"], 7),
+            weighted(&["C", "B", "A", "D", "E"], 2),
+            weighted(&["D", "C", "E", "B", "A"], 7),
+            weighted(&["E", "B", "A", "D", "C"], 8),
+        ];
+
+        let (outcome, pairwise, paths) = schulze(&options, &ballots);
+
+        // no option beats every other head to head, E wins on its strongest paths
+        assert_eq!(
+            outcome,
+            Outcome::Winner {
+                option: "E".to_string()
+            }
+        );
+        assert_eq!(pairwise[0][1], Uint128::new(20));
+        assert_eq!(pairwise[1][0], Uint128::new(25));
+        assert_eq!(
+            paths[4],
+            vec![25u128, 28, 28, 31, 0]
+                .into_iter()
+                .map(Uint128::new)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_schulze_unranked_options_share_last_place() {
+        let options = ranking(&["A", "B", "C"]);
+        let ballots = vec![weighted(&["A"], 2), weighted(&["B", "A"], 1)];
+
+        let (outcome, pairwise, _) = schulze(&options, &ballots);
+        assert_eq!(
+            outcome,
+            Outcome::Winner {
+                option: "A".to_string()
+            }
+        );
+        // the first ballot leaves B and C level, so it counts for neither over the other
+        assert_eq!(pairwise[1][2], Uint128::one());
+        assert_eq!(pairwise[2][1], Uint128::zero());
+    }
+
+    #[test]
+    fn test_single_transferable_vote_moves_surplus() {
+        let options = ranking(&["A", "B", "C"]);
+        let ballots = vec![
+            weighted(&["A", "B"], 6),
+            weighted(&["C"], 3),
+            weighted(&["B"], 2),
+        ];
+
+        let (outcome, quota, rounds) = single_transferable_vote(&options, &ballots, 2);
+
+        // quota is 11 / 3 + 1, A's surplus of 2 elects B
+        assert_eq!(quota, Uint128::new(4));
+        assert_eq!(
+            outcome,
+            Outcome::Elected {
+                options: ranking(&["A", "B"])
+            }
+        );
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].elected, ranking(&["A"]));
+        assert_eq!(
+            rounds[0].transfers,
+            vec![
+                ("B".to_string(), Decimal256::from_ratio(2u128, 1u128)),
+                ("C".to_string(), Decimal256::zero())
+            ]
+        );
+        assert_eq!(rounds[1].elected, ranking(&["B"]));
+    }
+
+    #[test]
+    fn test_single_transferable_vote_fills_seats_without_votes() {
+        let options = ranking(&["A", "B", "C"]);
+        let ballots = vec![weighted(&["A", "B"], 1)];
+
+        let (outcome, quota, rounds) = single_transferable_vote(&options, &ballots, 2);
+
+        // A meets the quota of 1 exactly and passes nothing on, C goes out as the last listed
+        // of the empty options and B takes the last seat with no votes
+        assert_eq!(quota, Uint128::new(1));
+        assert_eq!(
+            outcome,
+            Outcome::Elected {
+                options: ranking(&["A", "B"])
+            }
+        );
+        assert_eq!(rounds.len(), 3);
+        assert_eq!(rounds[1].eliminated, ranking(&["C"]));
+        assert_eq!(
+            rounds[2].tallies,
+            vec![("B".to_string(), Decimal256::zero())]
+        );
+    }
+
+    #[test]
+    fn test_single_transferable_vote_eliminates_lowest() {
+        let options = ranking(&["A", "B", "C"]);
+        let ballots = vec![
+            weighted(&["A"], 4),
+            weighted(&["B"], 3),
+            weighted(&["C", "B"], 2),
+        ];
+
+        let (outcome, _, rounds) = single_transferable_vote(&options, &ballots, 1);
+
+        // nobody reaches the quota of 5 until C is out and its ballots move to B
+        assert_eq!(
+            outcome,
+            Outcome::Elected {
+                options: ranking(&["B"])
+            }
+        );
+        assert_eq!(rounds[0].eliminated, ranking(&["C"]));
+        assert_eq!(rounds[1].elected, ranking(&["B"]));
+    }
+
+    #[test]
+    fn test_fund_projects_skips_what_does_not_fit() {
+        let tallies = vec![
+            ("Park".to_string(), Uint128::new(9)),
+            ("Library".to_string(), Uint128::new(7)),
+            ("Bench".to_string(), Uint128::new(3)),
+            ("Mural".to_string(), Uint128::zero()),
+        ];
+        let costs = [50u128, 80, 20, 10].map(Uint128::new);
+
+        let (funded, spent) = fund_projects(&tallies, &costs, Uint128::new(100));
+
+        // the library no longer fits after the park, the cheaper bench still does
+        assert_eq!(
+            funded,
+            vec![
+                ("Park".to_string(), Uint128::new(50)),
+                ("Bench".to_string(), Uint128::new(20))
+            ]
+        );
+        assert_eq!(spent, Uint128::new(70));
+    }
+
+    #[test]
+    fn test_estimate_weighted_median_and_quantiles() {
+        let estimates = vec![
+            (Decimal::percent(1200), Uint128::new(1)),
+            (Decimal::percent(1000), Uint128::new(2)),
+            (Decimal::percent(2000), Uint128::new(1)),
+        ];
+
+        let summary = estimate(&estimates, &[Decimal::percent(25), Decimal::percent(90)]).unwrap();
+
+        // sorted the weights run 10, 10, 12, 20: half the weight is reached at 10
+        assert_eq!(summary.mean, Decimal::percent(1300));
+        assert_eq!(summary.median, Decimal::percent(1000));
+        assert_eq!(
+            summary.quantiles,
+            vec![
+                (Decimal::percent(25), Decimal::percent(1000)),
+                (Decimal::percent(90), Decimal::percent(2000))
+            ]
+        );
+        assert_eq!(estimate(&[], &[]), None);
+    }
+
+    #[test]
+    fn test_bradley_terry_ranks_by_comparisons() {
+        let options = ranking(&["A", "B", "C", "D"]);
+        let comparisons = vec![
+            ("A", "B", Uint128::new(2)),
+            ("B", "A", Uint128::new(1)),
+            ("B", "C", Uint128::new(2)),
+            ("A", "C", Uint128::new(2)),
+        ];
+
+        let (outcome, ratings) = bradley_terry(&options, &comparisons);
+
+        assert_eq!(
+            outcome,
+            Outcome::Winner {
+                option: "A".to_string()
+            }
+        );
+        let ranking: Vec<&str> = ratings.iter().map(|(option, _)| option.as_str()).collect();
+        assert_eq!(ranking, vec!["A", "B", "D", "C"]);
+        // D was never compared and keeps the strength of the virtual opponent
+        assert_eq!(ratings[2].1, Decimal256::one());
+        assert!(ratings[3].1 < Decimal256::one());
+    }
+
+    #[test]
+    fn test_bradley_terry_ties_and_no_votes() {
+        let options = ranking(&["A", "B"]);
+
+        let (outcome, ratings) = bradley_terry(
+            &options,
+            &[("A", "B", Uint128::new(3)), ("B", "A", Uint128::new(3))],
+        );
+        assert_eq!(
+            outcome,
+            Outcome::Tie {
+                options: ranking(&["A", "B"])
+            }
+        );
+        assert_eq!(ratings[0].1, ratings[1].1);
+
+        let (outcome, ratings) = bradley_terry(&options, &[]);
+        assert_eq!(outcome, Outcome::NoVotes {});
+        assert_eq!(
+            ratings,
+            vec![
+                ("A".to_string(), Decimal256::one()),
+                ("B".to_string(), Decimal256::one())
+            ]
+        );
+    }
+}